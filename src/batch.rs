@@ -0,0 +1,50 @@
+use std::io::BufRead;
+
+use simple_math_parser::{Env, EvalOptions};
+
+use crate::{emit, Emit, Syntax};
+
+// How a batch run went. The exit status only says whether and how the run failed,
+// a count would clash with the statuses of the error classes, so `main` reports
+// `failed` and `lines` on stderr.
+pub struct Summary {
+    pub lines: usize,
+    pub failed: usize,
+    // exit status of the first failing line
+    pub first_failure: Option<u8>,
+}
+
+// Evaluates, or converts to the `output` format, every non-empty line of `input` on its own,
+// printing one result per line. Stops at the first failing line unless `keep_going` is set.
+pub fn run(
+    input: impl BufRead,
+    syntax: Syntax,
+    env: &Env,
+    options: &EvalOptions,
+    output: Emit,
+    keep_going: bool,
+) -> std::io::Result<Summary> {
+    let mut summary = Summary { lines: 0, failed: 0, first_failure: None };
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        summary.lines += 1;
+        match emit(&line, syntax, env, options, output) {
+            Ok(result) => println!("{}", result),
+            Err(failure) => {
+                summary.first_failure.get_or_insert(failure.exit_code());
+                eprintln!("{}", failure.into_diagnostic().render_at(&line, i + 1));
+                summary.failed += 1;
+                if !keep_going {
+                    break;
+                }
+            },
+        }
+    }
+
+    Ok(summary)
+}
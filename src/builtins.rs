@@ -0,0 +1,209 @@
+use std::fmt;
+
+// How many arguments a function takes, `max` is `None` for variadic functions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub const fn exact(n: usize) -> Self {
+        Arity { min: n, max: Some(n) }
+    }
+
+    pub const fn range(min: usize, max: usize) -> Self {
+        Arity { min, max: Some(max) }
+    }
+
+    pub const fn at_least(min: usize) -> Self {
+        Arity { min, max: None }
+    }
+
+    pub fn accepts(self, n: usize) -> bool {
+        n >= self.min && self.max.is_none_or(|max| n <= max)
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{} argument{}", max, plural(max)),
+            Some(max) => write!(f, "{} to {} arguments", self.min, max),
+            None => write!(f, "at least {} argument{}", self.min, plural(self.min)),
+        }
+    }
+}
+
+// Named constants, consulted after the variables of an `Env`
+pub const CONSTANTS: &[(&str, f64)] = &[
+    ("pi", std::f64::consts::PI),
+    ("e", std::f64::consts::E),
+    ("tau", std::f64::consts::TAU),
+    // the golden ratio
+    ("phi", 1.618_033_988_749_895),
+    ("inf", f64::INFINITY),
+    ("nan", f64::NAN),
+];
+
+pub(crate) fn constant(name: &str) -> Option<f64> {
+    CONSTANTS.iter().find(|(constant, _)| *constant == name).map(|&(_, value)| value)
+}
+
+// Functions fail with the reason an argument is outside of their domain
+pub(crate) type IntFn = fn(&[i128]) -> Result<i128, &'static str>;
+pub(crate) type FloatFn = fn(&[f64]) -> Result<f64, &'static str>;
+
+pub(crate) struct Builtin {
+    pub name: &'static str,
+    pub arity: Arity,
+    // `None` if the function only makes sense for floats, like `sqrt`
+    pub int: Option<IntFn>,
+    pub float: FloatFn,
+}
+
+pub(crate) fn lookup(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+    BUILTINS.iter().map(|builtin| builtin.name)
+}
+
+fn check(ok: bool, x: f64, reason: &'static str) -> Result<f64, &'static str> {
+    if ok { Ok(x) } else { Err(reason) }
+}
+
+fn integer(x: f64) -> Result<f64, &'static str> {
+    check(x.fract() == 0.0, x, "arguments must be integers")
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    (a, b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// Saturates at `i128::MAX`, which no `IntType` can hold
+fn lcm(a: i128, b: i128) -> i128 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    (a.abs() / gcd(a, b)).saturating_mul(b.abs())
+}
+
+fn float_gcd(mut a: f64, mut b: f64) -> f64 {
+    (a, b) = (a.abs(), b.abs());
+    while b != 0.0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn float_lcm(a: f64, b: f64) -> f64 {
+    if a == 0.0 || b == 0.0 {
+        return 0.0;
+    }
+    (a.abs() / float_gcd(a, b)) * b.abs()
+}
+
+const BUILTINS: &[Builtin] = &[
+    // powers and roots
+    Builtin {
+        name: "sqrt", arity: Arity::exact(1), int: None,
+        float: |a| check(a[0] >= 0.0, a[0].sqrt(), "square root of a negative number"),
+    },
+    Builtin { name: "cbrt", arity: Arity::exact(1), int: None, float: |a| Ok(a[0].cbrt()) },
+    Builtin { name: "hypot", arity: Arity::exact(2), int: None, float: |a| Ok(a[0].hypot(a[1])) },
+
+    // exponentials and logarithms
+    Builtin { name: "exp", arity: Arity::exact(1), int: None, float: |a| Ok(a[0].exp()) },
+    Builtin {
+        name: "ln", arity: Arity::exact(1), int: None,
+        float: |a| check(a[0] > 0.0, a[0].ln(), "logarithm of a non-positive number"),
+    },
+    Builtin {
+        name: "log2", arity: Arity::exact(1), int: None,
+        float: |a| check(a[0] > 0.0, a[0].log2(), "logarithm of a non-positive number"),
+    },
+    Builtin {
+        name: "log10", arity: Arity::exact(1), int: None,
+        float: |a| check(a[0] > 0.0, a[0].log10(), "logarithm of a non-positive number"),
+    },
+    // `log(x)` is base 10, `log(x, base)` any other base
+    Builtin {
+        name: "log", arity: Arity::range(1, 2), int: None,
+        float: |a| {
+            let base = a.get(1).copied().unwrap_or(10.0);
+            check(a[0] > 0.0, a[0], "logarithm of a non-positive number")?;
+            check(base > 0.0 && base != 1.0, a[0].log(base), "logarithm base must be positive and not 1")
+        },
+    },
+
+    // trigonometry, in radians
+    Builtin { name: "sin", arity: Arity::exact(1), int: None, float: |a| Ok(a[0].sin()) },
+    Builtin { name: "cos", arity: Arity::exact(1), int: None, float: |a| Ok(a[0].cos()) },
+    Builtin { name: "tan", arity: Arity::exact(1), int: None, float: |a| Ok(a[0].tan()) },
+    Builtin {
+        name: "asin", arity: Arity::exact(1), int: None,
+        float: |a| check((-1.0..=1.0).contains(&a[0]), a[0].asin(), "argument must be within [-1, 1]"),
+    },
+    Builtin {
+        name: "acos", arity: Arity::exact(1), int: None,
+        float: |a| check((-1.0..=1.0).contains(&a[0]), a[0].acos(), "argument must be within [-1, 1]"),
+    },
+    Builtin { name: "atan", arity: Arity::exact(1), int: None, float: |a| Ok(a[0].atan()) },
+    Builtin { name: "atan2", arity: Arity::exact(2), int: None, float: |a| Ok(a[0].atan2(a[1])) },
+    Builtin { name: "sinh", arity: Arity::exact(1), int: None, float: |a| Ok(a[0].sinh()) },
+    Builtin { name: "cosh", arity: Arity::exact(1), int: None, float: |a| Ok(a[0].cosh()) },
+    Builtin { name: "tanh", arity: Arity::exact(1), int: None, float: |a| Ok(a[0].tanh()) },
+
+    // rounding, a no-op on integers
+    Builtin { name: "floor", arity: Arity::exact(1), int: Some(|a| Ok(a[0])), float: |a| Ok(a[0].floor()) },
+    Builtin { name: "ceil", arity: Arity::exact(1), int: Some(|a| Ok(a[0])), float: |a| Ok(a[0].ceil()) },
+    // halfway cases round away from zero
+    Builtin { name: "round", arity: Arity::exact(1), int: Some(|a| Ok(a[0])), float: |a| Ok(a[0].round()) },
+    Builtin { name: "trunc", arity: Arity::exact(1), int: Some(|a| Ok(a[0])), float: |a| Ok(a[0].trunc()) },
+
+    // sign and magnitude
+    Builtin { name: "abs", arity: Arity::exact(1), int: Some(|a| Ok(a[0].abs())), float: |a| Ok(a[0].abs()) },
+    // -1, 0 or 1
+    Builtin {
+        name: "sign", arity: Arity::exact(1),
+        int: Some(|a| Ok(a[0].signum())),
+        float: |a| Ok(if a[0] == 0.0 { 0.0 } else { a[0].signum() }),
+    },
+
+    // comparison
+    Builtin {
+        name: "min", arity: Arity::at_least(1),
+        int: Some(|a| Ok(*a.iter().min().unwrap())),
+        float: |a| Ok(a.iter().copied().fold(f64::INFINITY, f64::min)),
+    },
+    Builtin {
+        name: "max", arity: Arity::at_least(1),
+        int: Some(|a| Ok(*a.iter().max().unwrap())),
+        float: |a| Ok(a.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+    },
+    // `clamp(x, lo, hi)`
+    Builtin {
+        name: "clamp", arity: Arity::exact(3),
+        int: Some(|a| if a[1] <= a[2] { Ok(a[0].clamp(a[1], a[2])) } else { Err("lower bound exceeds upper bound") }),
+        float: |a| check(a[1] <= a[2], a[0], "lower bound exceeds upper bound").map(|x| x.clamp(a[1], a[2])),
+    },
+
+    // number theory, always non-negative
+    Builtin {
+        name: "gcd", arity: Arity::at_least(1),
+        int: Some(|a| Ok(a.iter().fold(0, |acc, &n| gcd(acc, n)))),
+        float: |a| a.iter().try_fold(0.0, |acc, &x| Ok(float_gcd(acc, integer(x)?))),
+    },
+    Builtin {
+        name: "lcm", arity: Arity::at_least(1),
+        int: Some(|a| Ok(a.iter().fold(1, |acc, &n| lcm(acc, n)))),
+        float: |a| a.iter().try_fold(1.0, |acc, &x| Ok(float_lcm(acc, integer(x)?))),
+    },
+];
@@ -0,0 +1,165 @@
+use std::fmt::Write;
+
+use crate::{
+    error::{EvalError, ParseError},
+    span::Span,
+};
+
+// A rustc-style report: the message, the offending line with
+// the span underlined and an optional label and help note
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    pub label: Option<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Diagnostic { message: message.into(), span, label: None, help: None }
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    // Renders against the string the span points into, e.g.
+    //
+    // error: unmatched parenthesis
+    //   |
+    // 1 | 2 * (3 + 4
+    //   |     ^ unmatched `(` opened here
+    pub fn render(&self, source: &str) -> String {
+        self.render_at(source, 1)
+    }
+
+    // Like `render`, for a `source` that starts at line `first_line` of a larger input
+    pub fn render_at(&self, source: &str, first_line: usize) -> String {
+        let start = self.span.start.min(source.len());
+        let end = self.span.end.clamp(start, source.len());
+
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..].find('\n').map_or(source.len(), |i| start + i);
+        let line = &source[line_start..line_end];
+        let line_number = (source[..line_start].matches('\n').count() + first_line).to_string();
+
+        // columns are counted in chars, the underline stops at the end of the line
+        let column = source[line_start..start].chars().count();
+        let width = source[start..end.min(line_end)].chars().count().max(1);
+
+        let gutter = " ".repeat(line_number.len());
+        let mut out = String::new();
+        writeln!(out, "error: {}", self.message).unwrap();
+        writeln!(out, "{} |", gutter).unwrap();
+        writeln!(out, "{} | {}", line_number, line).unwrap();
+        write!(out, "{} | {}^{}", gutter, " ".repeat(column), "~".repeat(width - 1)).unwrap();
+        if let Some(label) = &self.label {
+            write!(out, " {}", label).unwrap();
+        }
+        if let Some(help) = &self.help {
+            write!(out, "\n{} = help: {}", gutter, help).unwrap();
+        }
+
+        out
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        let diagnostic = Diagnostic::new(error.to_string(), error.span());
+        match error {
+            ParseError::UnknownOperator(..) => diagnostic
+                .with_label("not a recognized operator")
+                .with_help("supported operators are `+`, `-`, `*`, `/`, `%`, `//`, `%%`, `^` (or `**`) and parentheses"),
+            ParseError::ExpectedOperand(_) => diagnostic
+                .with_label("expected a number, name or `(` here"),
+            ParseError::ExpectedOperator(_) => diagnostic
+                .with_label("expected an operator before this"),
+            ParseError::MissingLeftOperand(op, _) => diagnostic
+                .with_label(format!("operator `{}` is missing a left operand", op)),
+            ParseError::MissingRightOperand(op, _) => diagnostic
+                .with_label(format!("operator `{}` is missing a right operand", op)),
+            ParseError::UnmatchedParenOpen(_) => diagnostic
+                .with_label("unmatched `(` opened here"),
+            ParseError::UnmatchedParenClose(_) => diagnostic
+                .with_label("`)` has no matching `(`"),
+            ParseError::OutOfBounds(_) => diagnostic
+                .with_label("number literal is too large"),
+            ParseError::UnterminatedString(_) => diagnostic
+                .with_label("missing closing `\"`"),
+            ParseError::UnknownEscape(..) => diagnostic
+                .with_label("only `\\\"` and `\\\\` are supported"),
+            ParseError::StackUnderflow { needed, found, .. } => diagnostic
+                .with_label(format!(
+                    "takes {} operand{}, but the stack holds {}",
+                    needed, if *needed == 1 { "" } else { "s" }, found,
+                ))
+                .with_help("operands come before their operator, like `3 4 +`"),
+            ParseError::LeftoverOperands(..) => diagnostic
+                .with_label("not consumed by any operator")
+                .with_help("every operand but the result must be consumed, is an operator missing?"),
+            ParseError::MalformedCall(_) => diagnostic
+                .with_label("expected `name@count`, like `max@3`"),
+            ParseError::UnexpectedGrouping(..) => diagnostic
+                .with_label("grouping is implicit in RPN")
+                .with_help("calls are written after their arguments, like `1 2 max@2`"),
+            ParseError::ExpectedHead(_) => diagnostic
+                .with_label("a list starts with what it applies, like `(+ 1 2)` or `(max 1 2)`"),
+            ParseError::ExtraOperand(op, _) => diagnostic
+                .with_label(format!("`{}` takes at most two operands", op)),
+        }
+    }
+}
+
+impl From<&EvalError> for Diagnostic {
+    fn from(error: &EvalError) -> Self {
+        let diagnostic = Diagnostic::new(error.to_string(), error.span());
+        match error {
+            EvalError::DivisionByZero(_) => diagnostic
+                .with_label("right operand evaluates to zero"),
+            EvalError::NegativeExponent(_) => diagnostic
+                .with_label("exponent evaluates to a negative number")
+                .with_help("negative powers need floating-point mode"),
+            EvalError::Overflow(int_type, _) => diagnostic
+                .with_label(format!("result does not fit in `{}`", int_type)),
+            EvalError::OutOfBounds(int_type, _) => diagnostic
+                .with_label(format!("does not fit in `{}`", int_type)),
+            EvalError::NotAnInteger(_) => diagnostic
+                .with_label("fractional numbers need floating-point mode"),
+            EvalError::UnboundVariables(names) => diagnostic
+                .with_label(format!("`{}` has no value", names[0].0)),
+            EvalError::UnknownFunction(..) => diagnostic
+                .with_label("not a built-in or registered function"),
+            EvalError::Arity { found, .. } => diagnostic
+                .with_label(format!("called with {} argument{}", found, if *found == 1 { "" } else { "s" })),
+            EvalError::RequiresFloat(..) => diagnostic
+                .with_label("needs floating-point mode"),
+            EvalError::ArgumentType { expected, .. } => diagnostic
+                .with_label(format!("expected {}", expected)),
+            EvalError::UnexpectedString(_) => diagnostic
+                .with_label("strings can only be passed to host functions"),
+            // the reason goes under the call instead
+            EvalError::Domain { name, reason, span } => Diagnostic::new(format!("invalid argument to `{}`", name), *span)
+                .with_label(reason.as_str()),
+        }
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(error: ParseError) -> Self {
+        Diagnostic::from(&error)
+    }
+}
+
+impl From<EvalError> for Diagnostic {
+    fn from(error: EvalError) -> Self {
+        Diagnostic::from(&error)
+    }
+}
@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use crate::{
+    env::Env,
+    eval::EvalOptions,
+    expression::{ExprKind, Expression},
+    print::quoted,
+};
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+struct Graph<'a> {
+    out: String,
+    nodes: usize,
+    // where to evaluate subtrees for their annotations, if anywhere
+    values: Option<(&'a Env, &'a EvalOptions)>,
+}
+
+impl Graph<'_> {
+    // Writes `expr` and its subtrees, returns the name of its node
+    fn node(&mut self, expr: &Expression) -> String {
+        let name = format!("n{}", self.nodes);
+        self.nodes += 1;
+
+        let (label, shape) = match &expr.kind {
+            ExprKind::Operator { op, .. } => (op.to_string(), "circle"),
+            ExprKind::Unary { op, .. } => (op.to_string(), "circle"),
+            ExprKind::Constant(literal) => (literal.to_string(), "box"),
+            ExprKind::Variable(name) => (name.clone(), "box"),
+            ExprKind::Str(s) => (quoted(s), "box"),
+            ExprKind::Call { name, .. } => (format!("{}()", name), "ellipse"),
+        };
+        write!(self.out, "    {} [label=\"{}\", shape={}", name, escape(&label), shape).unwrap();
+
+        // strings have no value of their own, and impure calls would give each node a different one
+        if let Some((env, options)) = self.values.filter(|(env, _)| expr.is_pure(env)) {
+            if !matches!(expr.kind, ExprKind::Str(_)) {
+                let annotation = match expr.evaluate_with(env, options) {
+                    Ok(value) => format!("= {}", value),
+                    Err(e) => e.to_string(),
+                };
+                write!(self.out, ", xlabel=\"{}\"", escape(&annotation)).unwrap();
+            }
+        }
+        self.out.push_str("];\n");
+
+        let children: Vec<(String, &Expression)> = match &expr.kind {
+            ExprKind::Operator { lhs, rhs, .. } => {
+                vec![("lhs".to_string(), lhs.as_ref()), ("rhs".to_string(), rhs.as_ref())]
+            },
+            ExprKind::Unary { operand, .. } => vec![("operand".to_string(), operand.as_ref())],
+            ExprKind::Call { args, .. } => {
+                args.iter().enumerate().map(|(i, arg)| (format!("arg {}", i + 1), arg)).collect()
+            },
+            ExprKind::Constant(_) | ExprKind::Variable(_) | ExprKind::Str(_) => Vec::new(),
+        };
+        for (edge, child) in children {
+            let child = self.node(child);
+            writeln!(self.out, "    {} -> {} [label=\"{}\"];", name, child, edge).unwrap();
+        }
+
+        name
+    }
+}
+
+impl Expression {
+    fn dot(&self, values: Option<(&Env, &EvalOptions)>) -> String {
+        let mut graph = Graph { out: String::from("digraph expression {\n"), nodes: 0, values };
+        graph.node(self);
+        graph.out.push('}');
+        graph.out
+    }
+
+    // A Graphviz digraph of the tree, one node per subexpression with edges
+    // labelled `lhs`, `rhs`, `operand` or `arg <n>`. Render it with `dot -Tsvg`.
+    pub fn to_dot(&self) -> String {
+        self.dot(None)
+    }
+
+    // Like `to_dot`, with every node annotated with the value of its subtree or why it has none
+    pub fn to_dot_with_values(&self, env: &Env, options: &EvalOptions) -> String {
+        self.dot(Some((env, options)))
+    }
+}
@@ -0,0 +1,98 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{builtins, eval::Value, function::Function};
+
+// Variable bindings and host functions an expression is evaluated against
+#[derive(Clone, Debug)]
+pub struct Env {
+    vars: HashMap<String, Value>,
+    functions: HashMap<String, Function>,
+    // whether names fall back to `CONSTANTS`
+    constants: bool,
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Env { vars: HashMap::new(), functions: HashMap::new(), constants: true }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegisterError {
+    // built-ins cannot be replaced, see `builtin_names`
+    Builtin(String),
+    AlreadyRegistered(String),
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Builtin(name) => write!(f, "`{}` is a built-in function", name),
+            Self::AlreadyRegistered(name) => write!(f, "a function named `{}` is already registered", name),
+        }
+    }
+}
+
+impl std::error::Error for RegisterError {}
+
+impl Env {
+    pub fn new() -> Self {
+        Env::default()
+    }
+
+    // Binds `name`, returning the value it replaced
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<Value>) -> Option<Value> {
+        self.vars.insert(name.into(), value.into())
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.vars.get(name).copied()
+    }
+
+    // Resolves a name the way evaluation does: variables shadow `CONSTANTS`
+    pub fn lookup(&self, name: &str) -> Option<Value> {
+        self.get(name).or_else(|| {
+            self.constants.then(|| builtins::constant(name)).flatten().map(Value::Float)
+        })
+    }
+
+    // Disabling leaves only the variables set on this `Env`, for sandboxed evaluation
+    pub fn with_constants(mut self, enabled: bool) -> Self {
+        self.constants = enabled;
+        self
+    }
+
+    pub fn constants_enabled(&self) -> bool {
+        self.constants
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Value> {
+        self.vars.remove(name)
+    }
+
+    pub fn vars(&self) -> impl Iterator<Item = (&str, Value)> {
+        self.vars.iter().map(|(name, value)| (name.as_str(), *value))
+    }
+
+    // Makes `function` callable as `name(...)`
+    pub fn register(&mut self, name: impl Into<String>, function: Function) -> Result<(), RegisterError> {
+        let name = name.into();
+        if builtins::lookup(&name).is_some() {
+            return Err(RegisterError::Builtin(name));
+        }
+        if self.functions.contains_key(&name) {
+            return Err(RegisterError::AlreadyRegistered(name));
+        }
+
+        self.functions.insert(name, function);
+        Ok(())
+    }
+
+    pub fn unregister(&mut self, name: &str) -> Option<Function> {
+        self.functions.remove(name)
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.get(name)
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+
+use crate::{builtins::Arity, eval::IntType, span::Span, token::Op};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    UnknownOperator(char, Span),
+    ExpectedOperand(Span),
+    ExpectedOperator(Span),
+    MissingLeftOperand(Op, Span),
+    MissingRightOperand(Op, Span),
+    UnmatchedParenOpen(Span),
+    UnmatchedParenClose(Span),
+    OutOfBounds(Span),
+    UnterminatedString(Span),
+    UnknownEscape(char, Span),
+    // RPN: an operator or call with fewer operands on the stack than it takes
+    StackUnderflow {
+        word: String,
+        needed: usize,
+        found: usize,
+        span: Span,
+    },
+    // RPN and S-expressions: operands no operator consumed, spans all of them
+    LeftoverOperands(usize, Span),
+    // RPN: a call not written `name@count`
+    MalformedCall(Span),
+    // RPN: a parenthesis or comma, which only infix needs
+    UnexpectedGrouping(char, Span),
+    // S-expressions: a list that does not start with an operator or function name
+    ExpectedHead(Span),
+    // S-expressions: an operator applied to more than two operands, spans the first extra one
+    ExtraOperand(Op, Span),
+}
+
+impl ParseError {
+    // Where in the input the error was detected
+    pub fn span(&self) -> Span {
+        match self {
+            Self::UnknownOperator(_, span)
+            | Self::ExpectedOperand(span)
+            | Self::ExpectedOperator(span)
+            | Self::MissingLeftOperand(_, span)
+            | Self::MissingRightOperand(_, span)
+            | Self::UnmatchedParenOpen(span)
+            | Self::UnmatchedParenClose(span)
+            | Self::OutOfBounds(span)
+            | Self::UnterminatedString(span)
+            | Self::UnknownEscape(_, span)
+            | Self::StackUnderflow { span, .. }
+            | Self::LeftoverOperands(_, span)
+            | Self::MalformedCall(span)
+            | Self::UnexpectedGrouping(_, span)
+            | Self::ExpectedHead(span)
+            | Self::ExtraOperand(_, span) => *span,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownOperator(c, _) => write!(f, "unknown operator `{}`", c),
+            Self::ExpectedOperand(_)
+            | Self::MissingLeftOperand(..)
+            | Self::MissingRightOperand(..) => write!(f, "expected an operand"),
+            Self::ExpectedOperator(_) => write!(f, "expected an operator"),
+            Self::UnmatchedParenOpen(_)
+            | Self::UnmatchedParenClose(_) => write!(f, "unmatched parenthesis"),
+            Self::OutOfBounds(_) => write!(f, "constant out of bounds"),
+            Self::UnterminatedString(_) => write!(f, "unterminated string"),
+            Self::UnknownEscape(c, _) => write!(f, "unknown escape `\\{}`", c),
+            Self::StackUnderflow { word, .. } => write!(f, "not enough operands for `{}`", word),
+            Self::LeftoverOperands(1, _) => write!(f, "an operand is left over"),
+            Self::LeftoverOperands(n, _) => write!(f, "{} operands are left over", n),
+            Self::MalformedCall(_) => write!(f, "malformed function call"),
+            Self::UnexpectedGrouping(c, _) => write!(f, "unexpected `{}`", c),
+            Self::ExpectedHead(_) => write!(f, "expected an operator or function name"),
+            Self::ExtraOperand(..) => write!(f, "too many operands"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EvalError {
+    DivisionByZero(Span),
+    NegativeExponent(Span),
+    Overflow(IntType, Span),
+    OutOfBounds(IntType, Span),
+    NotAnInteger(Span),
+    // every unbound name with its first use, in order of appearance
+    UnboundVariables(Vec<(String, Span)>),
+    UnknownFunction(String, Span),
+    // spans point at the function name
+    Arity {
+        name: String,
+        expected: Arity,
+        found: usize,
+        span: Span,
+    },
+    RequiresFloat(String, Span),
+    // a host function argument could not be converted, spans the argument
+    ArgumentType {
+        name: String,
+        expected: &'static str,
+        span: Span,
+    },
+    UnexpectedString(Span),
+    // an argument is outside of what the function is defined for, spans the whole call
+    Domain {
+        name: String,
+        reason: String,
+        span: Span,
+    },
+}
+
+impl EvalError {
+    // The operator or operand that failed
+    pub fn span(&self) -> Span {
+        match self {
+            Self::DivisionByZero(span)
+            | Self::NegativeExponent(span)
+            | Self::Overflow(_, span)
+            | Self::OutOfBounds(_, span)
+            | Self::NotAnInteger(span)
+            | Self::UnexpectedString(span) => *span,
+            Self::UnboundVariables(names) => names[0].1,
+            Self::UnknownFunction(_, span)
+            | Self::RequiresFloat(_, span)
+            | Self::Arity { span, .. }
+            | Self::ArgumentType { span, .. }
+            | Self::Domain { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DivisionByZero(_) => write!(f, "division by zero"),
+            Self::NegativeExponent(_) => write!(f, "negative exponent"),
+            Self::Overflow(..) => write!(f, "arithmetic overflow"),
+            Self::OutOfBounds(..) => write!(f, "constant out of bounds"),
+            Self::NotAnInteger(_) => write!(f, "expected an integer"),
+            Self::UnboundVariables(names) => {
+                write!(f, "unbound variable{} ", if names.len() == 1 { "" } else { "s" })?;
+                for (i, (name, _)) in names.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "`{}`", name)?;
+                }
+                Ok(())
+            },
+            Self::UnknownFunction(name, _) => write!(f, "unknown function `{}`", name),
+            Self::Arity { name, expected, found, .. } => {
+                let verb = if *found == 1 { "was" } else { "were" };
+                write!(f, "`{}` takes {} but {} {} given", name, expected, found, verb)
+            },
+            Self::RequiresFloat(name, _) => write!(f, "`{}` is not defined for integers", name),
+            Self::ArgumentType { name, expected, .. } => {
+                write!(f, "mismatched argument to `{}`: expected {}", name, expected)
+            },
+            Self::UnexpectedString(_) => write!(f, "unexpected string"),
+            Self::Domain { name, reason, .. } => write!(f, "invalid argument to `{}`: {}", name, reason),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
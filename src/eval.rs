@@ -0,0 +1,575 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    builtins::{self, Builtin},
+    env::Env,
+    error::EvalError,
+    function::{Arg, CallError},
+    expression::{ExprKind, Expression},
+    span::Span,
+    token::{Literal, Op, UnaryOp},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    // fixed-width integer arithmetic, `/` truncates toward zero
+    #[default]
+    Integer,
+    // `f64` arithmetic following IEEE 754, `1/0` is infinity
+    Float,
+}
+
+// What integer arithmetic does with a result outside of `IntType`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overflow {
+    // report `EvalError::Overflow`
+    #[default]
+    Checked,
+    // keep the low bits, like two's complement hardware
+    Wrapping,
+    // clamp to the nearest representable value
+    Saturating,
+}
+
+impl FromStr for Overflow {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checked" => Ok(Overflow::Checked),
+            "wrapping" => Ok(Overflow::Wrapping),
+            "saturating" => Ok(Overflow::Saturating),
+            _ => Err(()),
+        }
+    }
+}
+
+// Integer type the arithmetic is carried out in. Only the constants below
+// and `FromStr` make one, so `bits` is always 8, 16, 32 or 64.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IntType {
+    bits: u32,
+    signed: bool,
+}
+
+impl IntType {
+    pub const I8: IntType = IntType { bits: 8, signed: true };
+    pub const I16: IntType = IntType { bits: 16, signed: true };
+    pub const I32: IntType = IntType { bits: 32, signed: true };
+    pub const I64: IntType = IntType { bits: 64, signed: true };
+    pub const U8: IntType = IntType { bits: 8, signed: false };
+    pub const U16: IntType = IntType { bits: 16, signed: false };
+    pub const U32: IntType = IntType { bits: 32, signed: false };
+    pub const U64: IntType = IntType { bits: 64, signed: false };
+
+    pub fn bits(self) -> u32 {
+        self.bits
+    }
+
+    pub fn is_signed(self) -> bool {
+        self.signed
+    }
+
+    pub fn min(self) -> i128 {
+        if self.signed { -(1 << (self.bits - 1)) } else { 0 }
+    }
+
+    pub fn max(self) -> i128 {
+        if self.signed { (1 << (self.bits - 1)) - 1 } else { (1 << self.bits) - 1 }
+    }
+
+    pub fn contains(self, n: i128) -> bool {
+        (self.min()..=self.max()).contains(&n)
+    }
+
+    // Keeps the low `bits` bits of `n`, sign-extended for signed types
+    pub fn wrap(self, n: i128) -> i128 {
+        let shift = 128 - self.bits;
+        if self.signed {
+            (n << shift) >> shift
+        } else {
+            ((n as u128) << shift >> shift) as i128
+        }
+    }
+}
+
+impl Default for IntType {
+    fn default() -> Self {
+        IntType::I32
+    }
+}
+
+impl FromStr for IntType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (signed, bits) = match s.split_at_checked(1) {
+            Some(("i", bits)) => (true, bits),
+            Some(("u", bits)) => (false, bits),
+            _ => return Err(()),
+        };
+
+        match bits {
+            "8" | "16" | "32" | "64" => Ok(IntType { bits: bits.parse().unwrap(), signed }),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for IntType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", if self.signed { "i" } else { "u" }, self.bits)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EvalOptions {
+    pub mode: Mode,
+    // integer mode only
+    pub overflow: Overflow,
+    pub int_type: IntType,
+}
+
+impl EvalOptions {
+    // Fits an integer result into `int_type`. `exact` is `None` if even `i128` overflowed,
+    // then `wrapped` holds the low bits and `negative` the sign of the true result.
+    fn fit(&self, exact: Option<i128>, wrapped: i128, negative: bool, span: Span) -> Result<i128, EvalError> {
+        let int_type = self.int_type;
+        match exact {
+            Some(n) if int_type.contains(n) => Ok(n),
+            _ => match self.overflow {
+                Overflow::Checked => Err(EvalError::Overflow(int_type, span)),
+                Overflow::Wrapping => Ok(int_type.wrap(wrapped)),
+                Overflow::Saturating if exact.map_or(negative, |n| n < 0) => Ok(int_type.min()),
+                Overflow::Saturating => Ok(int_type.max()),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    // wide enough for every `IntType`
+    Int(i128),
+    Float(f64),
+}
+
+impl From<i32> for Value {
+    fn from(n: i32) -> Self {
+        Value::Int(n.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Int(n.into())
+    }
+}
+
+impl From<u64> for Value {
+    fn from(n: u64) -> Self {
+        Value::Int(n.into())
+    }
+}
+
+impl From<i128> for Value {
+    fn from(n: i128) -> Self {
+        Value::Int(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Self {
+        Value::Float(x)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Float(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+fn lookup_builtin(name: &str, span: Span, argc: usize) -> Result<&'static Builtin, EvalError> {
+    let builtin = builtins::lookup(name).ok_or_else(|| EvalError::UnknownFunction(name.to_string(), span))?;
+    if !builtin.arity.accepts(argc) {
+        return Err(EvalError::Arity { name: name.to_string(), expected: builtin.arity, found: argc, span });
+    }
+
+    Ok(builtin)
+}
+
+// `a / b` rounded toward negative infinity, `b != 0`
+fn floor_div(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if a % b != 0 && (a < 0) != (b < 0) { q - 1 } else { q }
+}
+
+// `a^b` for `b >= 0`, `None` if it does not fit in `i128`
+fn checked_pow(a: i128, b: i128) -> Option<i128> {
+    match a {
+        // the only bases that survive exponents beyond `u32`
+        -1..=1 => Some(wrapping_pow(a, b)),
+        _ => a.checked_pow(b.try_into().ok()?),
+    }
+}
+
+// `a^b` modulo 2^128 for `b >= 0`, by squaring
+fn wrapping_pow(mut a: i128, mut b: i128) -> i128 {
+    let mut result: i128 = 1;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = result.wrapping_mul(a);
+        }
+        a = a.wrapping_mul(a);
+        b >>= 1;
+    }
+    result
+}
+
+impl Expression {
+    pub fn evaluate(&self, options: &EvalOptions) -> Result<Value, EvalError> {
+        self.evaluate_with(&Env::new(), options)
+    }
+
+    // Evaluates with variables looked up in `env`.
+    // Fails up front with every unbound name if any variable is missing.
+    pub fn evaluate_with(&self, env: &Env, options: &EvalOptions) -> Result<Value, EvalError> {
+        let mut unbound = Vec::new();
+        self.visit_variables(&mut |name, span| {
+            if env.lookup(name).is_none() && !unbound.iter().any(|(unbound, _)| unbound == name) {
+                unbound.push((name.to_string(), span));
+            }
+        });
+        if !unbound.is_empty() {
+            return Err(EvalError::UnboundVariables(unbound));
+        }
+
+        match options.mode {
+            Mode::Integer => self.evaluate_int(env, options).map(Value::Int),
+            Mode::Float => self.evaluate_float(env, options).map(Value::Float),
+        }
+    }
+
+    fn visit_variables<'a>(&'a self, f: &mut impl FnMut(&'a str, Span)) {
+        match &self.kind {
+            ExprKind::Operator { lhs, rhs, .. } => {
+                lhs.visit_variables(f);
+                rhs.visit_variables(f);
+            },
+            ExprKind::Unary { operand, .. } => operand.visit_variables(f),
+            ExprKind::Call { args, .. } => args.iter().for_each(|arg| arg.visit_variables(f)),
+            ExprKind::Variable(name) => f(name, self.span),
+            ExprKind::Constant(_) | ExprKind::Str(_) => {},
+        }
+    }
+
+    fn evaluate_int(&self, env: &Env, options: &EvalOptions) -> Result<i128, EvalError> {
+        match &self.kind {
+            ExprKind::Operator { op, op_span, lhs, rhs } => {
+                // operands are at most 64 bits wide, so only `Mul` and `Pow` can leave `i128`
+                let (a, b) = (lhs.evaluate_int(env, options)?, rhs.evaluate_int(env, options)?);
+                let (exact, wrapped) = match op {
+                    Op::Add => (Some(a + b), a + b),
+                    Op::Sub => (Some(a - b), a - b),
+                    Op::Mul => (a.checked_mul(b), a.wrapping_mul(b)),
+                    Op::Div | Op::Rem | Op::FloorDiv | Op::Mod if b == 0 => {
+                        return Err(EvalError::DivisionByZero(*op_span));
+                    },
+                    Op::Div => (Some(a / b), a / b),
+                    Op::Rem => (Some(a % b), a % b),
+                    Op::FloorDiv => {
+                        let q = floor_div(a, b);
+                        (Some(q), q)
+                    },
+                    Op::Mod => {
+                        let r = a - b * floor_div(a, b);
+                        (Some(r), r)
+                    },
+                    Op::Pow if b < 0 => return Err(EvalError::NegativeExponent(*op_span)),
+                    Op::Pow => (checked_pow(a, b), wrapping_pow(a, b)),
+                };
+                let negative = match op {
+                    Op::Pow => a < 0 && b % 2 == 1,
+                    _ => (a < 0) != (b < 0),
+                };
+                options.fit(exact, wrapped, negative, *op_span)
+            },
+            ExprKind::Unary { op, op_span, operand } => {
+                // `-128` fits in an `i8` even though `128` does not
+                if let (UnaryOp::Neg, ExprKind::Constant(Literal::Int(n))) = (op, &operand.kind) {
+                    let n = -i128::from(*n);
+                    if options.int_type.contains(n) {
+                        return Ok(n);
+                    }
+                }
+
+                let a = operand.evaluate_int(env, options)?;
+                match op {
+                    UnaryOp::Neg => options.fit(Some(-a), -a, a > 0, *op_span),
+                    UnaryOp::Pos => Ok(a),
+                }
+            },
+            ExprKind::Constant(Literal::Int(n)) => {
+                let n = i128::from(*n);
+                if options.int_type.contains(n) {
+                    Ok(n)
+                } else {
+                    Err(EvalError::OutOfBounds(options.int_type, self.span))
+                }
+            },
+            // whole, but wider than any `IntType`, like an integer literal past `u64::MAX`
+            ExprKind::Constant(Literal::Float(x)) if x.fract() == 0.0 && x.abs() >= u64::MAX as f64 => {
+                Err(EvalError::OutOfBounds(options.int_type, self.span))
+            },
+            ExprKind::Constant(Literal::Float(_)) => Err(EvalError::NotAnInteger(self.span)),
+            ExprKind::Str(_) => Err(EvalError::UnexpectedString(self.span)),
+            ExprKind::Variable(name) => match env.lookup(name) {
+                Some(Value::Int(n)) if options.int_type.contains(n) => Ok(n),
+                Some(Value::Int(_)) => Err(EvalError::OutOfBounds(options.int_type, self.span)),
+                Some(Value::Float(_)) => Err(EvalError::NotAnInteger(self.span)),
+                None => Err(EvalError::UnboundVariables(vec![(name.clone(), self.span)])),
+            },
+            ExprKind::Call { name, name_span, args } if env.function(name).is_some() => {
+                match self.call_host(env, options)? {
+                    Value::Int(n) => options.fit(Some(n), n, n < 0, *name_span),
+                    Value::Float(_) => Err(EvalError::NotAnInteger(self.span)),
+                }
+            },
+            ExprKind::Call { name, name_span, args } => {
+                let builtin = lookup_builtin(name, *name_span, args.len())?;
+                let int = builtin.int.ok_or_else(|| EvalError::RequiresFloat(name.clone(), *name_span))?;
+
+                let args = args.iter()
+                    .map(|arg| arg.evaluate_int(env, options))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let n = int(&args).map_err(|reason| EvalError::Domain {
+                    name: name.clone(),
+                    reason: reason.to_string(),
+                    span: self.span,
+                })?;
+
+                options.fit(Some(n), n, n < 0, *name_span)
+            },
+        }
+    }
+
+    fn evaluate_float(&self, env: &Env, options: &EvalOptions) -> Result<f64, EvalError> {
+        Ok(match &self.kind {
+            ExprKind::Operator { op, lhs, rhs, .. } => {
+                let (a, b) = (lhs.evaluate_float(env, options)?, rhs.evaluate_float(env, options)?);
+                match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                    Op::Rem => a % b,
+                    Op::FloorDiv => (a / b).floor(),
+                    Op::Mod => a - b * (a / b).floor(),
+                    Op::Pow => a.powf(b),
+                }
+            },
+            ExprKind::Unary { op, operand, .. } => {
+                let a = operand.evaluate_float(env, options)?;
+                match op {
+                    UnaryOp::Neg => -a,
+                    UnaryOp::Pos => a,
+                }
+            },
+            ExprKind::Constant(Literal::Int(n)) => *n as f64,
+            ExprKind::Constant(Literal::Float(x)) => *x,
+            ExprKind::Str(_) => return Err(EvalError::UnexpectedString(self.span)),
+            ExprKind::Variable(name) => match env.lookup(name) {
+                Some(Value::Int(n)) => n as f64,
+                Some(Value::Float(x)) => x,
+                None => return Err(EvalError::UnboundVariables(vec![(name.clone(), self.span)])),
+            },
+            ExprKind::Call { name, .. } if env.function(name).is_some() => {
+                match self.call_host(env, options)? {
+                    Value::Int(n) => n as f64,
+                    Value::Float(x) => x,
+                }
+            },
+            ExprKind::Call { name, name_span, args } => {
+                let builtin = lookup_builtin(name, *name_span, args.len())?;
+
+                let args = args.iter()
+                    .map(|arg| arg.evaluate_float(env, options))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (builtin.float)(&args).map_err(|reason| EvalError::Domain {
+                    name: name.clone(),
+                    reason: reason.to_string(),
+                    span: self.span,
+                })?
+            },
+        })
+    }
+
+    // Calls the host function `self` refers to, with numbers
+    // evaluated in the current mode and string literals passed as is
+    fn call_host(&self, env: &Env, options: &EvalOptions) -> Result<Value, EvalError> {
+        let ExprKind::Call { name, name_span, args } = &self.kind else { unreachable!() };
+        let function = env.function(name).unwrap();
+
+        if !function.arity().accepts(args.len()) {
+            return Err(EvalError::Arity {
+                name: name.clone(),
+                expected: function.arity(),
+                found: args.len(),
+                span: *name_span,
+            });
+        }
+
+        let values = args.iter()
+            .map(|arg| match &arg.kind {
+                ExprKind::Str(s) => Ok(Arg::Str(s.clone())),
+                _ => match options.mode {
+                    Mode::Integer => arg.evaluate_int(env, options).map(|n| Arg::Value(Value::Int(n))),
+                    Mode::Float => arg.evaluate_float(env, options).map(|x| Arg::Value(Value::Float(x))),
+                },
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        function.call(&values).map_err(|error| match error {
+            CallError::ArgumentType { index, expected } => EvalError::ArgumentType {
+                name: name.clone(),
+                expected,
+                span: args[index].span,
+            },
+            CallError::Domain(reason) => EvalError::Domain { name: name.clone(), reason, span: self.span },
+        })
+    }
+
+    // Whether evaluating twice in the same `Env` is guaranteed to give the same result,
+    // false if any host function marked `Function::impure` is called
+    pub fn is_pure(&self, env: &Env) -> bool {
+        match &self.kind {
+            ExprKind::Operator { lhs, rhs, .. } => lhs.is_pure(env) && rhs.is_pure(env),
+            ExprKind::Unary { operand, .. } => operand.is_pure(env),
+            ExprKind::Call { name, args, .. } => {
+                env.function(name).is_none_or(|function| function.is_pure())
+                    && args.iter().all(|arg| arg.is_pure(env))
+            },
+            ExprKind::Constant(_) | ExprKind::Variable(_) | ExprKind::Str(_) => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::tokenize;
+
+    fn evaluate(s: &str, int_type: IntType, overflow: Overflow) -> Result<i128, EvalError> {
+        let expr = tokenize(s).and_then(|tokens| Expression::parse(&tokens)).unwrap();
+        match expr.evaluate(&EvalOptions { mode: Mode::Integer, overflow, int_type })? {
+            Value::Int(n) => Ok(n),
+            Value::Float(x) => panic!("{} evaluated to a float {}", s, x),
+        }
+    }
+
+    // `s` under each policy: checked must overflow, wrapping and saturating give these
+    fn overflows(s: &str, int_type: IntType, wrapping: i128, saturating: i128) {
+        assert!(
+            matches!(evaluate(s, int_type, Overflow::Checked), Err(EvalError::Overflow(t, _)) if t == int_type),
+            "{} does not overflow {}",
+            s,
+            int_type,
+        );
+        assert_eq!(evaluate(s, int_type, Overflow::Wrapping), Ok(wrapping), "{} wrapping in {}", s, int_type);
+        assert_eq!(evaluate(s, int_type, Overflow::Saturating), Ok(saturating), "{} saturating in {}", s, int_type);
+    }
+
+    // `s` is representable, every policy gives `n`
+    fn fits(s: &str, int_type: IntType, n: i128) {
+        for overflow in [Overflow::Checked, Overflow::Wrapping, Overflow::Saturating] {
+            assert_eq!(evaluate(s, int_type, overflow), Ok(n), "{} in {} with {:?}", s, int_type, overflow);
+        }
+    }
+
+    #[test]
+    fn bounds() {
+        assert_eq!((IntType::I8.min(), IntType::I8.max()), (-128, 127));
+        assert_eq!((IntType::U8.min(), IntType::U8.max()), (0, 255));
+        assert_eq!((IntType::I64.min(), IntType::I64.max()), (i64::MIN.into(), i64::MAX.into()));
+        assert_eq!((IntType::U64.min(), IntType::U64.max()), (0, u64::MAX.into()));
+    }
+
+    #[test]
+    fn wrap() {
+        assert_eq!(IntType::I8.wrap(128), -128);
+        assert_eq!(IntType::I8.wrap(-129), 127);
+        assert_eq!(IntType::U8.wrap(256), 0);
+        assert_eq!(IntType::U8.wrap(-1), 255);
+        assert_eq!(IntType::I64.wrap(1 << 63), i64::MIN.into());
+        assert_eq!(IntType::U64.wrap(-1), u64::MAX.into());
+        assert_eq!(IntType::U64.wrap(i128::MIN), 0);
+    }
+
+    #[test]
+    fn signed_edges() {
+        fits("-128", IntType::I8, -128);
+        fits("127", IntType::I8, 127);
+        overflows("127 + 1", IntType::I8, -128, 127);
+        overflows("-128 - 1", IntType::I8, 127, -128);
+        overflows("-(-128)", IntType::I8, -128, 127);
+        overflows("-128 / -1", IntType::I8, -128, 127);
+        overflows("-128 // -1", IntType::I8, -128, 127);
+
+        let (min, max) = (i128::from(i64::MIN), i128::from(i64::MAX));
+        fits("-9223372036854775808", IntType::I64, min);
+        overflows("9223372036854775807 + 1", IntType::I64, min, max);
+        overflows("-9223372036854775808 // -1", IntType::I64, min, max);
+        overflows("-9223372036854775808 * -1", IntType::I64, min, max);
+        overflows("(-2) ^ 64", IntType::I64, 0, max);
+        overflows("(-2) ^ 65", IntType::I64, 0, min);
+        overflows("3 ^ 40", IntType::I64, IntType::I64.wrap(3i128.pow(40)), max);
+        overflows("(-3) ^ 41", IntType::I64, IntType::I64.wrap((-3i128).pow(41)), min);
+        // past `i128`, the sign comes from the base and the parity of the exponent
+        overflows("3 ^ 81", IntType::I64, IntType::I64.wrap(3i128.wrapping_pow(81)), max);
+        overflows("(-3) ^ 81", IntType::I64, IntType::I64.wrap((-3i128).wrapping_pow(81)), min);
+        overflows("(-2) ^ 100000000001", IntType::I64, 0, min);
+        overflows("(-2) ^ 100000000000", IntType::I64, 0, max);
+        overflows("9223372036854775807 * 9223372036854775807 * 9223372036854775807", IntType::I64, max, max);
+    }
+
+    #[test]
+    fn unsigned_edges() {
+        fits("255", IntType::U8, 255);
+        overflows("255 + 1", IntType::U8, 0, 255);
+        overflows("0 - 1", IntType::U8, 255, 0);
+        overflows("-1", IntType::U8, 255, 0);
+        overflows("16 * 16", IntType::U8, 0, 255);
+
+        let max = i128::from(u64::MAX);
+        fits("18446744073709551615", IntType::U64, max);
+        overflows("18446744073709551615 + 1", IntType::U64, 0, max);
+        overflows("0 - 18446744073709551615", IntType::U64, 1, 0);
+        overflows("18446744073709551615 * 18446744073709551615", IntType::U64, 1, max);
+        overflows("2 ^ 64", IntType::U64, 0, max);
+        overflows("2 ^ 100000000000", IntType::U64, 0, max);
+    }
+
+    #[test]
+    fn huge_exponents() {
+        for int_type in [IntType::I64, IntType::U64] {
+            fits("1 ^ 100000000000", int_type, 1);
+            fits("0 ^ 100000000000", int_type, 0);
+            fits("0 ^ 0", int_type, 1);
+        }
+        fits("(-1) ^ 100000000000", IntType::I64, 1);
+        fits("(-1) ^ 100000000001", IntType::I64, -1);
+        overflows("(-1) ^ 100000000001", IntType::U64, u64::MAX.into(), 0);
+    }
+
+    #[test]
+    fn literal_out_of_bounds() {
+        for overflow in [Overflow::Checked, Overflow::Wrapping, Overflow::Saturating] {
+            assert!(matches!(evaluate("128", IntType::I8, overflow), Err(EvalError::OutOfBounds(IntType::I8, _))));
+            assert!(matches!(evaluate("18446744073709551616", IntType::U64, overflow), Err(EvalError::OutOfBounds(..))));
+        }
+    }
+}
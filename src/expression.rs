@@ -0,0 +1,221 @@
+use std::{iter::Peekable, slice::Iter};
+
+use crate::{
+    error::ParseError,
+    span::Span,
+    token::{Assoc, Literal, Op, Token, TokenKind, UnaryOp},
+};
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
+pub enum ExprKind {
+    Operator {
+        op: Op,
+        op_span: Span,
+        lhs: Box<Expression>,
+        rhs: Box<Expression>,
+    },
+    Unary {
+        op: UnaryOp,
+        op_span: Span,
+        operand: Box<Expression>,
+    },
+    Constant(Literal),
+    Variable(String),
+    // only meaningful as an argument to a host function
+    Str(String),
+    Call {
+        name: String,
+        name_span: Span,
+        args: Vec<Expression>,
+    },
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Expression {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+// Equality is structural, spans are ignored so a re-parsed expression equals the original
+impl PartialEq for ExprKind {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                ExprKind::Operator { op, lhs, rhs, .. },
+                ExprKind::Operator { op: other_op, lhs: other_lhs, rhs: other_rhs, .. },
+            ) => op == other_op && lhs == other_lhs && rhs == other_rhs,
+            (
+                ExprKind::Unary { op, operand, .. },
+                ExprKind::Unary { op: other_op, operand: other_operand, .. },
+            ) => op == other_op && operand == other_operand,
+            (ExprKind::Constant(a), ExprKind::Constant(b)) => a == b,
+            (ExprKind::Variable(a), ExprKind::Variable(b)) | (ExprKind::Str(a), ExprKind::Str(b)) => a == b,
+            (
+                ExprKind::Call { name, args, .. },
+                ExprKind::Call { name: other_name, args: other_args, .. },
+            ) => name == other_name && args == other_args,
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq for Expression {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl Expression {
+    // Precedence climbing: parses operands joined by operators
+    // that bind at least as tight as `min_precedence`
+    fn parse_binary(
+        iter: &mut Peekable<Iter<Token>>,
+        end: Span,
+        min_precedence: u8,
+    ) -> Result<Self, ParseError> {
+        let mut lhs = Expression::parse_operand(iter, end)?;
+
+        while let Some(&&Token { kind: TokenKind::Operator(op), span: op_span }) = iter.peek() {
+            let precedence = op.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+
+            iter.next();
+
+            Expression::expect_right_operand(iter, op, op_span)?;
+
+            let next_min = match op.associativity() {
+                Assoc::Left => precedence + 1,
+                Assoc::Right => precedence,
+            };
+            let rhs = Expression::parse_binary(iter, end, next_min)?;
+
+            lhs = Expression {
+                span: lhs.span.to(rhs.span),
+                kind: ExprKind::Operator { op, op_span, lhs: Box::new(lhs), rhs: Box::new(rhs) },
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    fn expect_right_operand(
+        iter: &mut Peekable<Iter<Token>>,
+        op: Op,
+        op_span: Span,
+    ) -> Result<(), ParseError> {
+        match iter.peek() {
+            None | Some(Token { kind: TokenKind::ParenClose | TokenKind::Comma, .. }) => {
+                Err(ParseError::MissingRightOperand(op, op_span))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    // Arguments after the `(` of a call, returns them with the span of the closing `)`
+    fn parse_args(
+        iter: &mut Peekable<Iter<Token>>,
+        end: Span,
+        open: Span,
+    ) -> Result<(Vec<Self>, Span), ParseError> {
+        let mut args = Vec::new();
+
+        // `f()` takes no arguments, otherwise they are separated by `,`
+        if let Some(Token { kind: TokenKind::ParenClose, span }) = iter.peek() {
+            iter.next();
+            return Ok((args, *span));
+        }
+
+        loop {
+            args.push(Expression::parse_binary(iter, end, 0)?);
+            match iter.next() {
+                Some(Token { kind: TokenKind::Comma, .. }) => {},
+                Some(Token { kind: TokenKind::ParenClose, span }) => return Ok((args, *span)),
+                Some(other) => return Err(ParseError::ExpectedOperator(other.span)),
+                None => return Err(ParseError::UnmatchedParenOpen(open)),
+            }
+        }
+    }
+
+    fn parse_operand(iter: &mut Peekable<Iter<Token>>, end: Span) -> Result<Self, ParseError> {
+        let Some(token) = iter.next() else {
+            return Err(ParseError::ExpectedOperand(end));
+        };
+
+        match &token.kind {
+            TokenKind::Constant(literal) => Ok(Expression {
+                kind: ExprKind::Constant(*literal),
+                span: token.span,
+            }),
+
+            // a name followed by `(` is a call
+            TokenKind::Ident(name) if matches!(iter.peek(), Some(Token { kind: TokenKind::ParenOpen, .. })) => {
+                let open = iter.next().unwrap();
+                let (args, close) = Expression::parse_args(iter, end, open.span)?;
+
+                Ok(Expression {
+                    kind: ExprKind::Call { name: name.clone(), name_span: token.span, args },
+                    span: token.span.to(close),
+                })
+            },
+
+            TokenKind::Ident(name) => Ok(Expression {
+                kind: ExprKind::Variable(name.clone()),
+                span: token.span,
+            }),
+
+            TokenKind::Str(s) => Ok(Expression {
+                kind: ExprKind::Str(s.clone()),
+                span: token.span,
+            }),
+
+            TokenKind::ParenOpen => {
+                let mut expr = Expression::parse_binary(iter, end, 0)?;
+                match iter.next() {
+                    Some(close @ Token { kind: TokenKind::ParenClose, .. }) => {
+                        // the parenthesized expression covers its parens
+                        expr.span = token.span.to(close.span);
+                        Ok(expr)
+                    },
+                    Some(other) => Err(ParseError::ExpectedOperator(other.span)),
+                    None => Err(ParseError::UnmatchedParenOpen(token.span)),
+                }
+            },
+
+            &TokenKind::Operator(op) => {
+                let Some(unary) = UnaryOp::from_op(op) else {
+                    return Err(ParseError::MissingLeftOperand(op, token.span));
+                };
+
+                Expression::expect_right_operand(iter, op, token.span)?;
+                let operand = Expression::parse_binary(iter, end, UnaryOp::PRECEDENCE)?;
+
+                Ok(Expression {
+                    span: token.span.to(operand.span),
+                    kind: ExprKind::Unary { op: unary, op_span: token.span, operand: Box::new(operand) },
+                })
+            },
+            TokenKind::ParenClose | TokenKind::Comma => Err(ParseError::ExpectedOperand(token.span)),
+        }
+    }
+
+    // `tokens` must come from `tokenize`, spans are used for error reporting
+    pub fn parse(tokens: &[Token]) -> Result<Self, ParseError> {
+        // errors at the end of input point just past the last token
+        let end = tokens.last().map_or(0, |token| token.span.end);
+        let end = Span::new(end, end);
+
+        let mut iter = tokens.iter().peekable();
+        let expr = Expression::parse_binary(&mut iter, end, 0)?;
+
+        match iter.next() {
+            None => Ok(expr),
+            Some(Token { kind: TokenKind::ParenClose, span }) => Err(ParseError::UnmatchedParenClose(*span)),
+            Some(token) => Err(ParseError::ExpectedOperator(token.span)),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::{fmt, sync::Arc};
+
+use crate::{builtins::Arity, eval::Value};
+
+// An argument as passed to a host function: a number in the
+// current evaluation mode, or a string literal like `lookup("sku")`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Arg {
+    Value(Value),
+    Str(String),
+}
+
+// Conversion of an `Arg` into a parameter of a typed closure
+pub trait FromArg: Sized {
+    // Named in errors when the conversion fails
+    const EXPECTED: &'static str;
+
+    fn from_arg(arg: &Arg) -> Option<Self>;
+}
+
+impl FromArg for f64 {
+    const EXPECTED: &'static str = "a number";
+
+    fn from_arg(arg: &Arg) -> Option<Self> {
+        match arg {
+            Arg::Value(Value::Int(n)) => Some(*n as f64),
+            Arg::Value(Value::Float(x)) => Some(*x),
+            Arg::Str(_) => None,
+        }
+    }
+}
+
+// Integers accept floats without a fractional part
+macro_rules! impl_from_arg_int {
+    ($($int:ty),*) => {$(
+        impl FromArg for $int {
+            const EXPECTED: &'static str = concat!("an integer in range of `", stringify!($int), "`");
+
+            fn from_arg(arg: &Arg) -> Option<Self> {
+                match arg {
+                    Arg::Value(Value::Int(n)) => (*n).try_into().ok(),
+                    Arg::Value(Value::Float(x)) if x.fract() == 0.0 && x.abs() < 2f64.powi(127) => {
+                        (*x as i128).try_into().ok()
+                    },
+                    _ => None,
+                }
+            }
+        }
+    )*};
+}
+
+impl_from_arg_int!(i32, i64, i128, u32, u64);
+
+impl FromArg for String {
+    const EXPECTED: &'static str = "a string";
+
+    fn from_arg(arg: &Arg) -> Option<Self> {
+        match arg {
+            Arg::Str(s) => Some(s.clone()),
+            Arg::Value(_) => None,
+        }
+    }
+}
+
+impl FromArg for Arg {
+    const EXPECTED: &'static str = "anything";
+
+    fn from_arg(arg: &Arg) -> Option<Self> {
+        Some(arg.clone())
+    }
+}
+
+// Conversion of a closure's return value, `Err` is reported as a domain error
+pub trait IntoResult {
+    fn into_result(self) -> Result<Value, String>;
+}
+
+impl<T: Into<Value>> IntoResult for T {
+    fn into_result(self) -> Result<Value, String> {
+        Ok(self.into())
+    }
+}
+
+impl<T: Into<Value>, E: fmt::Display> IntoResult for Result<T, E> {
+    fn into_result(self) -> Result<Value, String> {
+        self.map(Into::into).map_err(|e| e.to_string())
+    }
+}
+
+// Why a host function call failed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CallError {
+    // argument `index` could not be converted, `expected` is `FromArg::EXPECTED`
+    ArgumentType { index: usize, expected: &'static str },
+    Domain(String),
+}
+
+type Call = dyn Fn(&[Arg]) -> Result<Value, CallError> + Send + Sync;
+
+// A function registered by the host, see `Env::register`
+#[derive(Clone)]
+pub struct Function {
+    arity: Arity,
+    pure: bool,
+    call: Arc<Call>,
+}
+
+impl Function {
+    // A pure function from a typed closure: `Function::new(|x: f64| x * 1.2)`
+    pub fn new<Args, F: IntoFunction<Args>>(f: F) -> Self {
+        f.into_function()
+    }
+
+    // A function taking its arguments untyped, for variadic functions
+    pub fn variadic<R: IntoResult>(
+        arity: Arity,
+        f: impl Fn(&[Arg]) -> R + Send + Sync + 'static,
+    ) -> Self {
+        Function {
+            arity,
+            pure: true,
+            call: Arc::new(move |args| f(args).into_result().map_err(CallError::Domain)),
+        }
+    }
+
+    // Marks the function as depending on something besides its arguments,
+    // like a clock or a database, see `Expression::is_pure`
+    pub fn impure(mut self) -> Self {
+        self.pure = false;
+        self
+    }
+
+    pub fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    pub fn is_pure(&self) -> bool {
+        self.pure
+    }
+
+    pub(crate) fn call(&self, args: &[Arg]) -> Result<Value, CallError> {
+        (self.call)(args)
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("arity", &self.arity)
+            .field("pure", &self.pure)
+            .finish_non_exhaustive()
+    }
+}
+
+// Closures whose parameters all implement `FromArg`
+pub trait IntoFunction<Args> {
+    fn into_function(self) -> Function;
+}
+
+macro_rules! impl_into_function {
+    ($n:literal $(, $index:literal $arg:ident)*) => {
+        impl<F, R, $($arg),*> IntoFunction<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + Send + Sync + 'static,
+            R: IntoResult,
+            $($arg: FromArg,)*
+        {
+            #[allow(non_snake_case, unused_variables)]
+            fn into_function(self) -> Function {
+                Function {
+                    arity: Arity::exact($n),
+                    pure: true,
+                    call: Arc::new(move |args| {
+                        $(
+                            let $arg = $arg::from_arg(&args[$index])
+                                .ok_or(CallError::ArgumentType { index: $index, expected: $arg::EXPECTED })?;
+                        )*
+                        self($($arg),*).into_result().map_err(CallError::Domain)
+                    }),
+                }
+            }
+        }
+    };
+}
+
+impl_into_function!(0);
+impl_into_function!(1, 0 A);
+impl_into_function!(2, 0 A, 1 B);
+impl_into_function!(3, 0 A, 1 B, 2 C);
+impl_into_function!(4, 0 A, 1 B, 2 C, 3 D);
@@ -0,0 +1,147 @@
+use crate::{
+    expression::{ExprKind, Expression},
+    print::{typeset_needs_parens, typeset_unary_needs_parens, Side},
+    token::{Literal, Op, UnaryOp},
+};
+
+fn group(out: &mut String, expr: &Expression, parens: bool) {
+    if parens {
+        out.push_str("\\left(");
+        latex(out, expr);
+        out.push_str("\\right)");
+    } else {
+        latex(out, expr);
+    }
+}
+
+fn args(out: &mut String, args: &[Expression]) {
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        latex(out, arg);
+    }
+}
+
+fn identifier(out: &mut String, name: &str) {
+    let name = name.replace('_', "\\_");
+    if name.chars().count() == 1 {
+        out.push_str(&name);
+    } else {
+        out.push_str(&format!("\\mathrm{{{}}}", name));
+    }
+}
+
+fn call(out: &mut String, name: &str, call_args: &[Expression]) {
+    // functions with their own notation
+    let wrap = match (name, call_args.len()) {
+        ("sqrt", 1) => Some(("\\sqrt{", "}")),
+        ("cbrt", 1) => Some(("\\sqrt[3]{", "}")),
+        ("abs", 1) => Some(("\\left|", "\\right|")),
+        ("floor", 1) => Some(("\\left\\lfloor ", " \\right\\rfloor")),
+        ("ceil", 1) => Some(("\\left\\lceil ", " \\right\\rceil")),
+        ("exp", 1) => Some(("e^{", "}")),
+        _ => None,
+    };
+    if let Some((open, close)) = wrap {
+        out.push_str(open);
+        latex(out, &call_args[0]);
+        out.push_str(close);
+        return;
+    }
+
+    let (operator, call_args) = match (name, call_args) {
+        ("log2", _) => ("\\log_{2}".to_string(), call_args),
+        ("log10", _) => ("\\log_{10}".to_string(), call_args),
+        ("log", [x, base]) => {
+            let mut operator = "\\log_{".to_string();
+            latex(&mut operator, base);
+            operator.push('}');
+            (operator, std::slice::from_ref(x))
+        },
+        ("asin" | "acos" | "atan", _) => (format!("\\arc{}", &name[1..]), call_args),
+        ("sin" | "cos" | "tan" | "sinh" | "cosh" | "tanh" | "ln" | "log" | "min" | "max" | "gcd", _) => {
+            (format!("\\{}", name), call_args)
+        },
+        _ => (format!("\\operatorname{{{}}}", name.replace('_', "\\_")), call_args),
+    };
+
+    out.push_str(&operator);
+    out.push_str("\\left(");
+    args(out, call_args);
+    out.push_str("\\right)");
+}
+
+fn latex(out: &mut String, expr: &Expression) {
+    match &expr.kind {
+        ExprKind::Operator { op: Op::Div, lhs, rhs, .. } => {
+            out.push_str("\\frac{");
+            latex(out, lhs);
+            out.push_str("}{");
+            latex(out, rhs);
+            out.push('}');
+        },
+        ExprKind::Operator { op: Op::FloorDiv, lhs, rhs, .. } => {
+            out.push_str("\\left\\lfloor \\frac{");
+            latex(out, lhs);
+            out.push_str("}{");
+            latex(out, rhs);
+            out.push_str("} \\right\\rfloor");
+        },
+        ExprKind::Operator { op: Op::Pow, lhs, rhs, .. } => {
+            group(out, lhs, typeset_needs_parens(Op::Pow, lhs, Side::Left));
+            out.push_str("^{");
+            latex(out, rhs);
+            out.push('}');
+        },
+        ExprKind::Operator { op, lhs, rhs, .. } => {
+            group(out, lhs, typeset_needs_parens(*op, lhs, Side::Left));
+            out.push_str(match op {
+                Op::Add => " + ",
+                Op::Sub => " - ",
+                Op::Mul => " \\cdot ",
+                Op::Rem => " \\operatorname{rem} ",
+                Op::Mod => " \\bmod ",
+                Op::Div | Op::FloorDiv | Op::Pow => unreachable!(),
+            });
+            group(out, rhs, typeset_needs_parens(*op, rhs, Side::Right));
+        },
+        ExprKind::Unary { op, operand, .. } => {
+            out.push_str(match op {
+                UnaryOp::Neg => "-",
+                UnaryOp::Pos => "+",
+            });
+            group(out, operand, typeset_unary_needs_parens(operand));
+        },
+        ExprKind::Constant(Literal::Float(x)) => {
+            // `6.02e23` as `6.02 \times 10^{23}`
+            let text = format!("{:?}", x);
+            match text.split_once('e') {
+                Some((mantissa, exponent)) => out.push_str(&format!("{} \\times 10^{{{}}}", mantissa, exponent)),
+                None => out.push_str(&text),
+            }
+        },
+        ExprKind::Constant(literal) => out.push_str(&literal.to_string()),
+        ExprKind::Variable(name) => match name.as_str() {
+            "pi" | "tau" | "phi" => out.push_str(&format!("\\{}", name)),
+            "inf" => out.push_str("\\infty"),
+            "nan" => out.push_str("\\mathrm{NaN}"),
+            _ => identifier(out, name),
+        },
+        ExprKind::Str(s) => {
+            let s = s.replace('\\', "\\textbackslash ").replace('{', "\\{").replace('}', "\\}");
+            out.push_str(&format!("\\text{{\"{}\"}}", s));
+        },
+        ExprKind::Call { name, args, .. } => call(out, name, args),
+    }
+}
+
+impl Expression {
+    // LaTeX math-mode source: `/` as `\frac{}{}`, `^` as a superscript,
+    // `*` as `\cdot` and `sqrt` as `\sqrt{}`, parenthesized only where needed
+    pub fn to_latex(&self) -> String {
+        let mut out = String::new();
+        latex(&mut out, self);
+        out
+    }
+}
@@ -0,0 +1,44 @@
+mod builtins;
+mod diagnostic;
+mod dot;
+mod env;
+mod error;
+mod eval;
+mod expression;
+mod function;
+mod latex;
+mod mathml;
+mod print;
+mod rpn;
+mod sexpr;
+mod span;
+mod token;
+mod typst;
+
+// With the `serde` feature, tokens and expressions serialize to JSON that
+// later versions keep reading. Enums with data are tagged by `type`, their
+// data goes in `value`, and every span is `{"start": <byte>, "end": <byte>}`:
+//
+// Token       {"kind": <TokenKind>, "span": <Span>}
+// TokenKind   {"type": "operator", "value": <Op>} | {"type": "constant", "value": <Literal>}
+//             | {"type": "ident", "value": "x"} | {"type": "str", "value": "s"}
+//             | {"type": "paren_open"} | {"type": "paren_close"} | {"type": "comma"}
+// Op          "add" | "sub" | "mul" | "div" | "rem" | "floor_div" | "mod" | "pow"
+// UnaryOp     "neg" | "pos"
+// Literal     {"type": "int", "value": 1} | {"type": "float", "value": 1.5}
+// Expression  {"kind": <ExprKind>, "span": <Span>}
+// ExprKind    {"type": "operator", "value": {"op": <Op>, "op_span": <Span>, "lhs": <Expression>, "rhs": <Expression>}}
+//             | {"type": "unary", "value": {"op": <UnaryOp>, "op_span": <Span>, "operand": <Expression>}}
+//             | {"type": "constant", "value": <Literal>} | {"type": "variable", "value": "x"}
+//             | {"type": "str", "value": "s"}
+//             | {"type": "call", "value": {"name": "f", "name_span": <Span>, "args": [<Expression>, ...]}}
+
+pub use builtins::{builtin_names, Arity, CONSTANTS};
+pub use diagnostic::Diagnostic;
+pub use env::{Env, RegisterError};
+pub use error::{EvalError, ParseError};
+pub use eval::{EvalOptions, IntType, Mode, Overflow, Value};
+pub use expression::{ExprKind, Expression};
+pub use function::{Arg, CallError, FromArg, Function, IntoFunction, IntoResult};
+pub use span::Span;
+pub use token::{tokenize, Assoc, Literal, Op, Token, TokenKind, UnaryOp};
@@ -1,167 +1,343 @@
-use std::{iter::{Peekable, Rev}, slice::Iter};
+mod batch;
+#[cfg(feature = "repl")]
+mod repl;
 
-macro_rules! errexit {
-    ($reason:literal) => {
-        println!("Error: {}", $reason);
-        std::process::exit(-1);
-    };
-}
+use std::{fs::File, io::BufReader, path::PathBuf, process::ExitCode, str::FromStr};
 
-// Operators
-#[derive(Clone, Copy, Debug)]
-enum Op {
-    Add,
-    Sub,
-    Mul,
-    Div
-}
+use simple_math_parser::{tokenize, Diagnostic, Env, EvalOptions, Expression, Mode, Value};
 
-#[derive(Debug)]
-enum Token {
-    Operator(Op),
+const USAGE: &str = "\
+usage: simple-math-parser [options] [--] [expression | - | --file <path>]
 
-    Constant(u32),
+Without an expression, starts an interactive session, which only evaluates:
+--emit, --latex, --dump-tokens and --dump-ast need an expression, `-` or
+`--file`. With `-` or `--file`, evaluates every non-empty line of stdin or
+<path> on its own and prints one result per line. After `--`, the expression
+is read as is even if it looks like an option.
 
-    ParenOpen,
-    ParenClose,
-}
+options:
+  --float                  evaluate with 64-bit floating-point numbers
+  --overflow <policy>      checked (default), wrapping or saturating
+  --width <type>           integer type: i8, i16, i32 (default), i64, u8, u16, u32 or u64
+  --var <name>=<value>     bind a variable, may be repeated
+  --no-constants           do not predefine pi, e, tau, phi, inf and nan
+  --file <path>            read expressions from <path>
+  --keep-going             in batch mode, continue after a line fails
+  --input <syntax>         read expressions as infix (default), rpn, like `3 4 + 2 *`,
+                           or sexpr, like `(* (+ 3 4) 2)`
+  --emit <format>          print the expression instead of its value,
+                           as infix, rpn, sexpr, latex, mathml, typst or dot (Graphviz)
+  --annotate               with --emit dot, label every node with its value
+  --latex                  same as --emit latex
+  --dump-tokens            print the tokens of the expression as JSON,
+                           not with --input rpn, which is split into words instead
+  --dump-ast               print the parsed expression as JSON
+  --help                   print this message
 
-fn tokenize(s: &str) -> Vec<Token> {
-    let mut tokens = Vec::new();
-    let mut iter = s.chars().peekable();
+exit status:
+  0  success
+  1  an input file could not be read
+  2  invalid command line
+  3  syntax error
+  4  evaluation error, like division by zero or overflow
 
-    while let Some(c) = iter.peek() {
-        // parse a digit
-        if c.is_numeric() {
-            let mut constant: u32 = 0;
-            while let Some(digit) = iter.peek() {
-                if !digit.is_numeric() {
-                    break;
-                }
+Errors go to stderr. In batch mode, the status is that of the first failing
+line, so it stays one of the above, and `<n> of <m> lines failed` is the last
+line on stderr.";
 
-                constant *= 10;
-                constant += digit.to_digit(10).unwrap();
+// Exit statuses, as documented in `USAGE`
+const EXIT_IO: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_SYNTAX: u8 = 3;
+const EXIT_EVAL: u8 = 4;
 
-                iter.next();
-            }
+// Why an input failed, tokenizing and parsing both count as `Syntax`
+enum Failure {
+    Syntax(Diagnostic),
+    Eval(Diagnostic),
+}
 
-            tokens.push(Token::Constant(constant));
-            continue;
+impl Failure {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Failure::Syntax(_) => EXIT_SYNTAX,
+            Failure::Eval(_) => EXIT_EVAL,
         }
+    }
 
-        // parse an operator / parenthesis
-        if !c.is_whitespace() {
-            tokens.push(match c {
-                '+' => Token::Operator(Op::Add),
-                '-' => Token::Operator(Op::Sub),
-                '*' => Token::Operator(Op::Mul),
-                '/' => Token::Operator(Op::Div),
-                '(' => Token::ParenOpen,
-                ')' => Token::ParenClose,
-                _   => {
-                    errexit!("Unknown operator!");
-                }
-            });
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Failure::Syntax(diagnostic) | Failure::Eval(diagnostic) => diagnostic,
         }
-
-        iter.next();
     }
-
-    return tokens;
 }
 
-enum Expression {
-    Operator(Op, Box<Expression>, Box<Expression>),
-    Constant(i32)
+// How inputs are written
+#[derive(Clone, Copy)]
+enum Syntax {
+    Infix,
+    Rpn,
+    Sexpr,
 }
 
-impl Expression {
-    fn parse_block(
-        iter: &mut Peekable<Rev<Iter<Token>>>,
-        is_paren_block: bool,
-    ) -> Self {
-        let mut expr: Option<Expression> = None; // self
-        let mut operand: Option<Expression> = None;
+impl FromStr for Syntax {
+    type Err = ();
 
-        let mut paren_close_matched = false; // used if is_paren_block is true
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "infix" => Ok(Syntax::Infix),
+            "rpn" => Ok(Syntax::Rpn),
+            "sexpr" => Ok(Syntax::Sexpr),
+            _ => Err(()),
+        }
+    }
+}
 
-        while let Some(&token) = iter.peek() {
-            // ParenOpen is a special case
-            if let Token::ParenOpen = token {
-                    // consume the paren if it belongs to this block
-                    if is_paren_block {
-                        paren_close_matched = true;
-                        iter.next();
-                    }
+// What to print for each input
+#[derive(Clone, Copy)]
+enum Emit {
+    Value,
+    Infix,
+    Rpn,
+    Sexpr,
+    Latex,
+    MathMl,
+    Typst,
+    Dot { values: bool },
+    // JSON, needs the `serde` feature
+    Tokens,
+    Ast,
+}
 
-                    break;
-            }
+impl FromStr for Emit {
+    type Err = ();
 
-            iter.next();
+    // `Value` is the default and has no name
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "infix" => Ok(Emit::Infix),
+            "rpn" => Ok(Emit::Rpn),
+            "sexpr" => Ok(Emit::Sexpr),
+            "latex" => Ok(Emit::Latex),
+            "mathml" => Ok(Emit::MathMl),
+            "typst" => Ok(Emit::Typst),
+            "dot" => Ok(Emit::Dot { values: false }),
+            _ => Err(()),
+        }
+    }
+}
 
-            match token {
-                Token::Constant(n) => {
-                    operand = Some(Expression::Constant(
-                        (*n).try_into().unwrap_or_else(|_| { errexit!("Out of bounds!"); })
-                    ));
-                },
+enum Input {
+    Expression(String),
+    Stdin,
+    File(PathBuf),
+}
 
-                Token::Operator(op) => {
-                    expr = Some(Expression::Operator(
-                        *op,
-                        Box::new(Expression::parse_block(iter, false)),
-                        Box::new(operand.unwrap_or_else(|| { errexit!("Expected an operand!"); }))
-                    ));
-                    operand = None;
-                },
+struct Args {
+    options: EvalOptions,
+    env: Env,
+    input: Option<Input>,
+    syntax: Syntax,
+    emit: Emit,
+    keep_going: bool,
+    help: bool,
+}
 
-                Token::ParenClose => {
-                    operand = Some(Expression::parse_block(iter, true));
-                },
+// `None` on anything the usage does not allow
+fn parse_args(mut args: impl Iterator<Item = String>) -> Option<Args> {
+    let mut parsed = Args {
+        options: EvalOptions::default(),
+        env: Env::new(),
+        input: None,
+        syntax: Syntax::Infix,
+        emit: Emit::Value,
+        keep_going: false,
+        help: false,
+    };
+    let mut annotate = false;
+    let mut options_done = false;
 
-                Token::ParenOpen => unreachable!(),
-            }
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-" if parsed.input.is_none() => parsed.input = Some(Input::Stdin),
+            // after `--`, `--5` is an expression
+            _ if options_done && parsed.input.is_none() => parsed.input = Some(Input::Expression(arg)),
+            _ if options_done => return None,
+            "--" => options_done = true,
+            "--float" => parsed.options.mode = Mode::Float,
+            "--overflow" => parsed.options.overflow = args.next()?.parse().ok()?,
+            "--width" => parsed.options.int_type = args.next()?.parse().ok()?,
+            "--no-constants" => parsed.env = parsed.env.with_constants(false),
+            "--var" => {
+                let binding = args.next()?;
+                let (name, value) = parse_binding(&binding)?;
+                parsed.env.set(name, value);
+            },
+            "--keep-going" => parsed.keep_going = true,
+            "--input" => parsed.syntax = args.next()?.parse().ok()?,
+            "--emit" => parsed.emit = args.next()?.parse().ok()?,
+            "--latex" => parsed.emit = Emit::Latex,
+            "--annotate" => annotate = true,
+            "--dump-tokens" => parsed.emit = Emit::Tokens,
+            "--dump-ast" => parsed.emit = Emit::Ast,
+            "--help" | "-h" => parsed.help = true,
+            "--file" if parsed.input.is_none() => parsed.input = Some(Input::File(args.next()?.into())),
+            // an unknown option, `--5` is a double negation
+            _ if is_long_option(&arg) => return None,
+            _ if parsed.input.is_none() => parsed.input = Some(Input::Expression(arg)),
+            _ => return None,
         }
+    }
 
-        if is_paren_block && !paren_close_matched {
-            errexit!("Unmatched parenthesis!");
-        }
+    // only infix and S-expression input go through `tokenize`
+    if matches!((parsed.emit, parsed.syntax), (Emit::Tokens, Syntax::Rpn)) {
+        return None;
+    }
 
-        if let Some(expr) = expr {
-            expr
-        } else {
-            operand.unwrap_or_else(|| { errexit!("Expected an operand!"); })
-        }
+    if annotate {
+        let Emit::Dot { values } = &mut parsed.emit else {
+            return None;
+        };
+        *values = true;
     }
 
-    pub fn parse(tokens: &[Token]) -> Self {
-        Expression::parse_block(
-            &mut tokens.iter().rev().peekable(),
-            false
-        )
+    Some(parsed)
+}
+
+// `--` followed by a letter, like `--float`
+fn is_long_option(arg: &str) -> bool {
+    arg.strip_prefix("--").is_some_and(|name| name.starts_with(|c: char| c.is_ascii_alphabetic()))
+}
+
+// A name that can be bound, the names the tokenizer reads as one identifier
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// `name=value`, where name is an identifier and value an integer or a float
+fn parse_binding(binding: &str) -> Option<(&str, Value)> {
+    let (name, value) = binding.split_once('=')?;
+    if !is_identifier(name) {
+        return None;
     }
+    let value = match value.parse::<i128>() {
+        Ok(n) => Value::Int(n),
+        Err(_) => Value::Float(value.parse().ok()?),
+    };
 
-    pub fn evaluate(&self) -> i32 {
-        match self {
-            Self::Operator(op, a, b) => {
-                match op {
-                    Op::Add => a.evaluate() + b.evaluate(),
-                    Op::Sub => a.evaluate() - b.evaluate(),
-                    Op::Mul => a.evaluate() * b.evaluate(),
-                    Op::Div => a.evaluate() / b.evaluate(),
-                }
-            },
-            Self::Constant(n) => *n,
-        }
+    Some((name, value))
+}
+
+// Diagnostics from here on are meant to be rendered against `input`
+fn parse(input: &str, syntax: Syntax) -> Result<Expression, Failure> {
+    let expr = match syntax {
+        Syntax::Infix => tokenize(input).and_then(|tokens| Expression::parse(&tokens)),
+        Syntax::Rpn => Expression::parse_rpn(input),
+        Syntax::Sexpr => Expression::parse_sexpr(input),
+    };
+    expr.map_err(|e| Failure::Syntax(e.into()))
+}
+
+// Runs `input` through the whole pipeline
+fn evaluate(input: &str, syntax: Syntax, env: &Env, options: &EvalOptions) -> Result<Value, Failure> {
+    parse(input, syntax)?.evaluate_with(env, options).map_err(|e| Failure::Eval(e.into()))
+}
+
+// The line to print for `input`
+fn emit(input: &str, syntax: Syntax, env: &Env, options: &EvalOptions, emit: Emit) -> Result<String, Failure> {
+    match emit {
+        Emit::Value => evaluate(input, syntax, env, options).map(|value| value.to_string()),
+        Emit::Infix => parse(input, syntax).map(|expr| expr.to_string()),
+        Emit::Rpn => parse(input, syntax).map(|expr| expr.to_rpn()),
+        Emit::Sexpr => parse(input, syntax).map(|expr| expr.to_sexpr()),
+        Emit::Latex => parse(input, syntax).map(|expr| expr.to_latex()),
+        Emit::MathMl => parse(input, syntax).map(|expr| expr.to_mathml()),
+        Emit::Typst => parse(input, syntax).map(|expr| expr.to_typst()),
+        Emit::Dot { values: false } => parse(input, syntax).map(|expr| expr.to_dot()),
+        Emit::Dot { values: true } => parse(input, syntax).map(|expr| expr.to_dot_with_values(env, options)),
+        #[cfg(feature = "serde")]
+        Emit::Tokens => tokenize(input).map(|tokens| to_json(&tokens)).map_err(|e| Failure::Syntax(e.into())),
+        #[cfg(feature = "serde")]
+        Emit::Ast => parse(input, syntax).map(|expr| to_json(&expr)),
+        // rejected by `main`
+        #[cfg(not(feature = "serde"))]
+        Emit::Tokens | Emit::Ast => unreachable!(),
     }
 }
 
-fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() != 2 {
-        println!("usage: simple-math-parser <expression>");
-        return;
+#[cfg(feature = "serde")]
+fn to_json(value: &impl serde::Serialize) -> String {
+    // the library types always serialize
+    serde_json::to_string(value).unwrap()
+}
+
+fn main() -> ExitCode {
+    let Some(args) = parse_args(std::env::args().skip(1)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(EXIT_USAGE);
+    };
+
+    if args.help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    if cfg!(not(feature = "serde")) && matches!(args.emit, Emit::Tokens | Emit::Ast) {
+        eprintln!("error: --dump-tokens and --dump-ast need the `serde` feature, rebuild with `--features serde`");
+        return ExitCode::from(EXIT_USAGE);
     }
 
-    println!("{}", Expression::parse(&tokenize(args[1].as_str())).evaluate()); // 10
+    let Some(input) = args.input else {
+        // the interactive session only evaluates
+        if !matches!(args.emit, Emit::Value) {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+
+        #[cfg(feature = "repl")]
+        {
+            repl::run(args.env, &args.options, args.syntax);
+            return ExitCode::SUCCESS;
+        }
+        #[cfg(not(feature = "repl"))]
+        {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let summary = match input {
+        Input::Expression(input) => {
+            return match emit(&input, args.syntax, &args.env, &args.options, args.emit) {
+                Ok(output) => {
+                    println!("{}", output);
+                    ExitCode::SUCCESS
+                },
+                Err(failure) => {
+                    let code = failure.exit_code();
+                    eprintln!("{}", failure.into_diagnostic().render(&input));
+                    ExitCode::from(code)
+                },
+            };
+        },
+        Input::Stdin => batch::run(std::io::stdin().lock(), args.syntax, &args.env, &args.options, args.emit, args.keep_going),
+        Input::File(path) => File::open(&path)
+            .and_then(|file| batch::run(BufReader::new(file), args.syntax, &args.env, &args.options, args.emit, args.keep_going))
+            .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", path.display(), e))),
+    };
+
+    match summary {
+        Ok(summary) => match summary.first_failure {
+            None => ExitCode::SUCCESS,
+            Some(code) => {
+                eprintln!("{} of {} lines failed", summary.failed, summary.lines);
+                ExitCode::from(code)
+            },
+        },
+        Err(e) => {
+            eprintln!("error: cannot read {}", e);
+            ExitCode::from(EXIT_IO)
+        },
+    }
 }
@@ -0,0 +1,195 @@
+use crate::{
+    expression::{ExprKind, Expression},
+    print::{typeset_needs_parens, typeset_unary_needs_parens, Side},
+    token::{Literal, Op, UnaryOp},
+};
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn element(out: &mut String, tag: &str, content: &str) {
+    out.push_str(&format!("<{}>{}</{}>", tag, content, tag));
+}
+
+fn operator(out: &mut String, content: &str) {
+    element(out, "mo", content);
+}
+
+// Every expression renders as one element, which children of `mfrac`, `msup` and the like must be
+fn group(out: &mut String, expr: &Expression, parens: bool) {
+    if parens {
+        out.push_str("<mrow>");
+        operator(out, "(");
+        mathml(out, expr);
+        operator(out, ")");
+        out.push_str("</mrow>");
+    } else {
+        mathml(out, expr);
+    }
+}
+
+fn fraction(out: &mut String, numerator: &Expression, denominator: &Expression) {
+    out.push_str("<mfrac>");
+    mathml(out, numerator);
+    mathml(out, denominator);
+    out.push_str("</mfrac>");
+}
+
+// `open`, `expr` and `close` in a row, for delimiters like `|x|`
+fn delimited(out: &mut String, open: &str, expr: &Expression, close: &str) {
+    out.push_str("<mrow>");
+    operator(out, open);
+    mathml(out, expr);
+    operator(out, close);
+    out.push_str("</mrow>");
+}
+
+fn call(out: &mut String, name: &str, call_args: &[Expression]) {
+    // functions with their own notation
+    match (name, call_args) {
+        ("sqrt", [x]) => {
+            out.push_str("<msqrt>");
+            mathml(out, x);
+            out.push_str("</msqrt>");
+            return;
+        },
+        ("cbrt", [x]) => {
+            out.push_str("<mroot>");
+            mathml(out, x);
+            element(out, "mn", "3");
+            out.push_str("</mroot>");
+            return;
+        },
+        ("abs", [x]) => return delimited(out, "|", x, "|"),
+        ("floor", [x]) => return delimited(out, "&#x230A;", x, "&#x230B;"),
+        ("ceil", [x]) => return delimited(out, "&#x2308;", x, "&#x2309;"),
+        ("exp", [x]) => {
+            out.push_str("<msup>");
+            element(out, "mi", "e");
+            mathml(out, x);
+            out.push_str("</msup>");
+            return;
+        },
+        _ => {},
+    }
+
+    out.push_str("<mrow>");
+    let call_args = match (name, call_args) {
+        ("log2" | "log10", _) => {
+            out.push_str("<msub>");
+            element(out, "mi", "log");
+            element(out, "mn", &name[3..]);
+            out.push_str("</msub>");
+            call_args
+        },
+        ("log", [x, base]) => {
+            out.push_str("<msub>");
+            element(out, "mi", "log");
+            mathml(out, base);
+            out.push_str("</msub>");
+            std::slice::from_ref(x)
+        },
+        ("asin" | "acos" | "atan", _) => {
+            element(out, "mi", &format!("arc{}", &name[1..]));
+            call_args
+        },
+        _ => {
+            element(out, "mi", name);
+            call_args
+        },
+    };
+
+    // function application, invisible but read out by screen readers
+    operator(out, "&#x2061;");
+    out.push_str("<mrow>");
+    operator(out, "(");
+    for (i, arg) in call_args.iter().enumerate() {
+        if i > 0 {
+            operator(out, ",");
+        }
+        mathml(out, arg);
+    }
+    operator(out, ")");
+    out.push_str("</mrow></mrow>");
+}
+
+fn mathml(out: &mut String, expr: &Expression) {
+    match &expr.kind {
+        ExprKind::Operator { op: Op::Div, lhs, rhs, .. } => fraction(out, lhs, rhs),
+        ExprKind::Operator { op: Op::FloorDiv, lhs, rhs, .. } => {
+            out.push_str("<mrow>");
+            operator(out, "&#x230A;");
+            fraction(out, lhs, rhs);
+            operator(out, "&#x230B;");
+            out.push_str("</mrow>");
+        },
+        ExprKind::Operator { op: Op::Pow, lhs, rhs, .. } => {
+            out.push_str("<msup>");
+            group(out, lhs, typeset_needs_parens(Op::Pow, lhs, Side::Left));
+            mathml(out, rhs);
+            out.push_str("</msup>");
+        },
+        ExprKind::Operator { op, lhs, rhs, .. } => {
+            out.push_str("<mrow>");
+            group(out, lhs, typeset_needs_parens(*op, lhs, Side::Left));
+            operator(out, match op {
+                Op::Add => "+",
+                Op::Sub => "&#x2212;",
+                Op::Mul => "&#x22C5;",
+                Op::Rem => "rem",
+                Op::Mod => "mod",
+                Op::Div | Op::FloorDiv | Op::Pow => unreachable!(),
+            });
+            group(out, rhs, typeset_needs_parens(*op, rhs, Side::Right));
+            out.push_str("</mrow>");
+        },
+        ExprKind::Unary { op, operand, .. } => {
+            out.push_str("<mrow>");
+            operator(out, match op {
+                UnaryOp::Neg => "&#x2212;",
+                UnaryOp::Pos => "+",
+            });
+            group(out, operand, typeset_unary_needs_parens(operand));
+            out.push_str("</mrow>");
+        },
+        ExprKind::Constant(Literal::Float(x)) => {
+            // `6.02e23` as `6.02 × 10²³`
+            let s = format!("{:?}", x);
+            match s.split_once('e') {
+                Some((mantissa, exponent)) => {
+                    out.push_str("<mrow>");
+                    element(out, "mn", mantissa);
+                    operator(out, "&#xD7;");
+                    out.push_str("<msup>");
+                    element(out, "mn", "10");
+                    element(out, "mn", exponent);
+                    out.push_str("</msup></mrow>");
+                },
+                None => element(out, "mn", &s),
+            }
+        },
+        ExprKind::Constant(literal) => element(out, "mn", &literal.to_string()),
+        ExprKind::Variable(name) => element(out, "mi", match name.as_str() {
+            "pi" => "&#x3C0;",
+            "tau" => "&#x3C4;",
+            "phi" => "&#x3C6;",
+            "inf" => "&#x221E;",
+            "nan" => "NaN",
+            _ => name,
+        }),
+        ExprKind::Str(s) => element(out, "ms", &escape(s)),
+        ExprKind::Call { name, args, .. } => call(out, name, args),
+    }
+}
+
+impl Expression {
+    // A Presentation MathML `<math>` element: `/` as `<mfrac>`, `^` as `<msup>` and
+    // `*` as `⋅`, parenthesized only where needed
+    pub fn to_mathml(&self) -> String {
+        let mut out = String::from("<math xmlns=\"http://www.w3.org/1998/Math/MathML\">");
+        mathml(&mut out, self);
+        out.push_str("</math>");
+        out
+    }
+}
@@ -0,0 +1,217 @@
+use std::fmt;
+
+use crate::{
+    expression::{ExprKind, Expression},
+    token::{Assoc, Literal, Op, Token, TokenKind, UnaryOp},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Side {
+    Left,
+    Right,
+}
+
+// How tightly the top node of `expr` binds, operands that are not operators bind tightest
+fn precedence(expr: &Expression) -> u8 {
+    match &expr.kind {
+        ExprKind::Operator { op, .. } => op.precedence(),
+        ExprKind::Unary { .. } => UnaryOp::PRECEDENCE,
+        _ => u8::MAX,
+    }
+}
+
+// Whether `child` needs parentheses as the `side` operand of `op`.
+// This is the grouping every infix backend shares.
+pub(crate) fn needs_parens(op: Op, child: &Expression, side: Side) -> bool {
+    match (&child.kind, side) {
+        // a prefix operator on the right starts a fresh operand, `2 ^ -1`
+        (ExprKind::Unary { .. }, Side::Right) => false,
+        _ => match (op.associativity(), side) {
+            (Assoc::Left, Side::Left) | (Assoc::Right, Side::Right) => precedence(child) < op.precedence(),
+            _ => precedence(child) <= op.precedence(),
+        },
+    }
+}
+
+// Whether `operand` needs parentheses under a prefix operator
+pub(crate) fn unary_needs_parens(operand: &Expression) -> bool {
+    precedence(operand) < UnaryOp::PRECEDENCE
+}
+
+// Whether `expr` is typeset with its own delimiters, like a fraction
+fn self_delimited(expr: &Expression) -> bool {
+    matches!(expr.kind, ExprKind::Operator { op: Op::Div | Op::FloorDiv, .. })
+}
+
+// Whether `expr` can carry a superscript without parentheses
+fn is_atom(expr: &Expression) -> bool {
+    match &expr.kind {
+        // `6.02e23` is typeset as a product
+        ExprKind::Constant(literal) => !literal.to_string().contains('e'),
+        // `exp(x)` is typeset as `e^x`, which cannot take a second superscript
+        ExprKind::Call { name, args, .. } => !(name == "exp" && args.len() == 1),
+        ExprKind::Variable(_) | ExprKind::Str(_) => true,
+        ExprKind::Operator { .. } | ExprKind::Unary { .. } => false,
+    }
+}
+
+// Like `needs_parens`, for the backends that typeset `/` as a fraction and `^` as a
+// superscript. The layout itself groups fraction parts and exponents.
+pub(crate) fn typeset_needs_parens(op: Op, child: &Expression, side: Side) -> bool {
+    match (op, side) {
+        (Op::Div | Op::FloorDiv, _) | (Op::Pow, Side::Right) => false,
+        (Op::Pow, Side::Left) => !is_atom(child),
+        _ => !self_delimited(child) && needs_parens(op, child, side),
+    }
+}
+
+pub(crate) fn typeset_unary_needs_parens(operand: &Expression) -> bool {
+    !self_delimited(operand) && unary_needs_parens(operand)
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Literal::Int(n) => write!(f, "{}", n),
+            // `Debug` keeps the `.0` and switches to exponents, so floats re-tokenize as floats
+            Literal::Float(x) => write!(f, "{:?}", x),
+        }
+    }
+}
+
+// `s` as a string literal `tokenize` reads back as `s`
+pub(crate) fn quoted(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Operator(op) => write!(f, "{}", op),
+            TokenKind::Constant(literal) => write!(f, "{}", literal),
+            TokenKind::Ident(name) => f.write_str(name),
+            TokenKind::Str(s) => f.write_str(&quoted(s)),
+            TokenKind::ParenOpen => f.write_str("("),
+            TokenKind::ParenClose => f.write_str(")"),
+            TokenKind::Comma => f.write_str(","),
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+fn write_grouped(f: &mut fmt::Formatter, expr: &Expression, parens: bool) -> fmt::Result {
+    if parens {
+        write!(f, "({})", expr)
+    } else {
+        write!(f, "{}", expr)
+    }
+}
+
+// Canonical infix with only the parentheses precedence and associativity require,
+// e.g. `(1 + 2) * -x ^ 2`. Parsing the output gives back an equal expression.
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ExprKind::Operator { op, lhs, rhs, .. } => {
+                write_grouped(f, lhs, needs_parens(*op, lhs, Side::Left))?;
+                write!(f, " {} ", op)?;
+                write_grouped(f, rhs, needs_parens(*op, rhs, Side::Right))
+            },
+            ExprKind::Unary { op, operand, .. } => {
+                write!(f, "{}", op)?;
+                // `- -x` rather than `--x`
+                if matches!(operand.kind, ExprKind::Unary { .. }) {
+                    f.write_str(" ")?;
+                }
+                write_grouped(f, operand, unary_needs_parens(operand))
+            },
+            ExprKind::Constant(literal) => write!(f, "{}", literal),
+            ExprKind::Variable(name) => f.write_str(name),
+            ExprKind::Str(s) => f.write_str(&quoted(s)),
+            ExprKind::Call { name, args, .. } => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                f.write_str(")")
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expression::Expression, token::tokenize};
+
+    fn parse(s: &str) -> Expression {
+        tokenize(s).and_then(|tokens| Expression::parse(&tokens)).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let cases = [
+            "1 + 2 * 3",
+            "(1 + 2) * 3",
+            "1 - (2 - 3)",
+            "(1 - 2) - 3",
+            "2 ^ 3 ^ 2",
+            "(2 ^ 3) ^ 2",
+            "-(2 ^ 3)",
+            "(-2) ^ 3",
+            "(-a) ^ -b",
+            "2 ^ -x ^ 2",
+            "2 ^ (-x) ^ 2",
+            "a - -b",
+            "a--b",
+            "-(-x)",
+            "+(-x)",
+            "-(+(-x))",
+            "-(a + b)",
+            "-(a * b)",
+            "(a * b) % c",
+            "a * (b % c)",
+            "a / (b * c)",
+            "(a // b) %% c",
+            "a %% (b // c)",
+            "a - (b + c)",
+            "a ** b ** c",
+            "1.5e300 * 2.0",
+            "max(1, -2, (3 + 4) * 5)",
+            "f()",
+            "-sqrt(x) ^ 2",
+            "g(\"a \\\"b\\\" c\", x)",
+        ];
+
+        for case in cases {
+            let expr = parse(case);
+            assert_eq!(parse(&expr.to_string()), expr, "{} printed as {}", case, expr);
+        }
+    }
+
+    #[test]
+    fn minimal_parentheses() {
+        let cases = [
+            ("((1 + 2)) * (3)", "(1 + 2) * 3"),
+            ("(1 * 2) + 3", "1 * 2 + 3"),
+            ("2 ^ (3 ^ 2)", "2 ^ 3 ^ 2"),
+            ("(2 ^ 3) ^ 2", "(2 ^ 3) ^ 2"),
+            ("-(2 ^ 2)", "-2 ^ 2"),
+            ("-(-x)", "- -x"),
+            ("+(-x)", "+ -x"),
+            ("a-(-b)", "a - -b"),
+            ("max(1,2)", "max(1, 2)"),
+        ];
+
+        for (input, printed) in cases {
+            assert_eq!(parse(input).to_string(), printed);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+
+use rustyline::{error::ReadlineError, DefaultEditor};
+use simple_math_parser::{builtin_names, Diagnostic, Env, EvalOptions, Span, Value, CONSTANTS};
+
+use crate::{evaluate, is_identifier, Syntax};
+
+const HELP: &str = "\
+Enter an expression to evaluate it, the result is kept in `ans`.
+
+  let <name> = <expression>    evaluate and bind to <name>
+  :vars                        list bound variables
+  :help                        show this message
+  :quit                        leave, as does Ctrl-D";
+
+// `$XDG_DATA_HOME/simple-math-parser/history`, falling back to `~/.local/share`
+fn history_path() -> Option<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+
+    Some(data_home.join("simple-math-parser").join("history"))
+}
+
+// Evaluates a line, binding the result to `ans` and,
+// for `let <name> = <expression>`, to `name` as well
+fn evaluate_line(line: &str, syntax: Syntax, env: &mut Env, options: &EvalOptions) -> Result<Value, Diagnostic> {
+    let mut name = None;
+    let mut offset = 0;
+
+    if let Some(binding) = line.strip_prefix("let").filter(|rest| rest.starts_with(char::is_whitespace)) {
+        let Some((lhs, _)) = binding.split_once('=') else {
+            return Err(Diagnostic::new("expected `=`", Span::new(line.len(), line.len()))
+                .with_help("bindings are written `let <name> = <expression>`"));
+        };
+
+        let lhs_start = 3 + lhs.len() - lhs.trim_start().len();
+        if !is_identifier(lhs.trim()) {
+            return Err(Diagnostic::new("expected a variable name", Span::new(lhs_start, lhs_start + lhs.trim().len()))
+                .with_help("names start with a letter or `_`, followed by letters, digits or `_`"));
+        }
+
+        name = Some(lhs.trim());
+        offset = 3 + lhs.len() + 1;
+    }
+
+    // spans are relative to the expression, shift them back into the line
+    let value = evaluate(&line[offset..], syntax, env, options).map_err(|failure| {
+        let mut diagnostic = failure.into_diagnostic();
+        diagnostic.span = Span::new(diagnostic.span.start + offset, diagnostic.span.end + offset);
+        diagnostic
+    })?;
+
+    if let Some(name) = name {
+        env.set(name, value);
+    }
+    env.set("ans", value);
+
+    Ok(value)
+}
+
+pub fn run(mut env: Env, options: &EvalOptions, syntax: Syntax) {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("error: cannot start line editor: {}", e);
+            return;
+        },
+    };
+
+    let history = history_path();
+    if let Some(path) = &history {
+        // there is no history on the first run
+        let _ = editor.load_history(path);
+    }
+
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            // Ctrl-C drops the current line
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                break;
+            },
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+
+        match line {
+            ":quit" | ":q" => break,
+            ":help" => {
+                println!("{}", HELP);
+                println!();
+                println!("functions: {}", builtin_names().collect::<Vec<_>>().join(", "));
+                println!("constants: {}", CONSTANTS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", "));
+            },
+            ":vars" => {
+                let mut vars: Vec<_> = env.vars().collect();
+                vars.sort_by_key(|(name, _)| *name);
+                for (name, value) in vars {
+                    println!("{} = {}", name, value);
+                }
+            },
+            _ if line.starts_with(':') => eprintln!("error: unknown command `{}`, see `:help`", line),
+            _ => match evaluate_line(line, syntax, &mut env, options) {
+                Ok(value) => println!("{}", value),
+                Err(diagnostic) => eprintln!("{}", diagnostic.render(line)),
+            },
+        }
+    }
+
+    if let Some(path) = &history {
+        let saved = path.parent().map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| editor.save_history(path).map_err(std::io::Error::other));
+        if let Err(e) = saved {
+            eprintln!("error: cannot save history to {}: {}", path.display(), e);
+        }
+    }
+}
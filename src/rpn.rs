@@ -0,0 +1,198 @@
+use crate::{
+    error::ParseError,
+    expression::{ExprKind, Expression},
+    print::quoted,
+    span::Span,
+    token::{scan_string, tokenize, tokenize_from, Token, TokenKind, UnaryOp},
+};
+
+// Prefix operators get names of their own, `-` is always binary in RPN
+fn unary_word(op: UnaryOp) -> &'static str {
+    match op {
+        UnaryOp::Neg => "neg",
+        UnaryOp::Pos => "pos",
+    }
+}
+
+fn unary_from_word(word: &str) -> Option<UnaryOp> {
+    match word {
+        "neg" => Some(UnaryOp::Neg),
+        "pos" => Some(UnaryOp::Pos),
+        _ => None,
+    }
+}
+
+fn write_rpn(words: &mut Vec<String>, expr: &Expression) {
+    match &expr.kind {
+        ExprKind::Operator { op, lhs, rhs, .. } => {
+            write_rpn(words, lhs);
+            write_rpn(words, rhs);
+            words.push(op.symbol().to_string());
+        },
+        ExprKind::Unary { op, operand, .. } => {
+            write_rpn(words, operand);
+            words.push(unary_word(*op).to_string());
+        },
+        ExprKind::Constant(literal) => words.push(literal.to_string()),
+        ExprKind::Variable(name) => words.push(name.clone()),
+        ExprKind::Str(s) => words.push(quoted(s)),
+        ExprKind::Call { name, args, .. } => {
+            for arg in args {
+                write_rpn(words, arg);
+            }
+            words.push(format!("{}@{}", name, args.len()));
+        },
+    }
+}
+
+// Whitespace-separated words with their spans, a string literal is one word even with spaces in it
+fn words(s: &str) -> Result<Vec<Span>, ParseError> {
+    let mut words = Vec::new();
+    let mut start = 0;
+
+    while let Some(len) = s[start..].find(|c: char| !c.is_whitespace()) {
+        start += len;
+        let end = if s[start..].starts_with('"') {
+            scan_string(s, start)?.1
+        } else {
+            s[start..].find(char::is_whitespace).map_or(s.len(), |len| start + len)
+        };
+
+        words.push(Span::new(start, end));
+        start = end;
+    }
+
+    Ok(words)
+}
+
+// The top `n` operands, the deepest first
+fn pop(stack: &mut Vec<Expression>, n: usize, word: &str, span: Span) -> Result<Vec<Expression>, ParseError> {
+    if stack.len() < n {
+        return Err(ParseError::StackUnderflow { word: word.to_string(), needed: n, found: stack.len(), span });
+    }
+    Ok(stack.split_off(stack.len() - n))
+}
+
+// `name@count`
+fn parse_call(stack: &mut Vec<Expression>, word: &str, span: Span) -> Result<Expression, ParseError> {
+    let (name, count) = word.split_once('@').unwrap();
+    let is_name = matches!(tokenize(name).as_deref(), Ok([Token { kind: TokenKind::Ident(_), .. }]));
+    let Some(count) = count.parse().ok().filter(|_| is_name) else {
+        return Err(ParseError::MalformedCall(span));
+    };
+
+    let args = pop(stack, count, word, span)?;
+    Ok(Expression {
+        span: args.first().map_or(span, |arg| arg.span.to(span)),
+        kind: ExprKind::Call {
+            name: name.to_string(),
+            name_span: Span::new(span.start, span.start + name.len()),
+            args,
+        },
+    })
+}
+
+impl Expression {
+    // Postfix notation, e.g. `1 2 3 * +`. Prefix operators are written `neg` and `pos`,
+    // so variables of those names do not survive the round trip, and a call as its
+    // arguments followed by `name@count`.
+    pub fn to_rpn(&self) -> String {
+        let mut words = Vec::new();
+        write_rpn(&mut words, self);
+        words.join(" ")
+    }
+
+    // Parses what `to_rpn` writes. Operands and operators that `tokenize` can tell apart
+    // need no whitespace between them, a call needs it around `name@count`.
+    pub fn parse_rpn(s: &str) -> Result<Self, ParseError> {
+        let mut stack = Vec::new();
+
+        for span in words(s)? {
+            let word = &s[span.start..span.end];
+            if word.contains('@') && !word.starts_with('"') {
+                let call = parse_call(&mut stack, word, span)?;
+                stack.push(call);
+                continue;
+            }
+
+            for token in tokenize_from(&s[..span.end], span.start)? {
+                let text = &s[token.span.start..token.span.end];
+                let expr = match token.kind {
+                    TokenKind::Constant(literal) => Expression { kind: ExprKind::Constant(literal), span: token.span },
+                    TokenKind::Str(string) => Expression { kind: ExprKind::Str(string), span: token.span },
+                    TokenKind::Ident(name) => match unary_from_word(&name) {
+                        Some(op) => {
+                            let operand = pop(&mut stack, 1, text, token.span)?.pop().unwrap();
+                            Expression {
+                                span: operand.span.to(token.span),
+                                kind: ExprKind::Unary { op, op_span: token.span, operand: Box::new(operand) },
+                            }
+                        },
+                        None => Expression { kind: ExprKind::Variable(name), span: token.span },
+                    },
+                    TokenKind::Operator(op) => {
+                        let [lhs, rhs] = <[_; 2]>::try_from(pop(&mut stack, 2, text, token.span)?).unwrap();
+                        Expression {
+                            span: lhs.span.to(token.span),
+                            kind: ExprKind::Operator { op, op_span: token.span, lhs: Box::new(lhs), rhs: Box::new(rhs) },
+                        }
+                    },
+                    TokenKind::ParenOpen | TokenKind::ParenClose | TokenKind::Comma => {
+                        return Err(ParseError::UnexpectedGrouping(text.chars().next().unwrap(), token.span));
+                    },
+                };
+                stack.push(expr);
+            }
+        }
+
+        match stack.len() {
+            0 => Err(ParseError::ExpectedOperand(Span::new(s.len(), s.len()))),
+            1 => Ok(stack.pop().unwrap()),
+            n => Err(ParseError::LeftoverOperands(n - 1, stack[0].span.to(stack[n - 2].span))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expression::Expression, token::tokenize};
+
+    fn parse(s: &str) -> Expression {
+        tokenize(s).and_then(|tokens| Expression::parse(&tokens)).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let cases = [
+            "1 + 2 * 3",
+            "(1 - 2) - 3",
+            "1 - (2 - 3)",
+            "2 ^ 3 ^ 2",
+            "-(2 ^ 3)",
+            "(-a) ^ -b",
+            "2 ^ -x ^ 2",
+            "a - -b",
+            "-(+(-x))",
+            "(a * b) % c",
+            "(a // b) %% c",
+            "1.5e300 * 2.0",
+            "max(1, -2, (3 + 4) * 5)",
+            "f()",
+            "g(h(x), \"a \\\"b\\\" c\")",
+        ];
+
+        for case in cases {
+            let expr = parse(case);
+            let rpn = expr.to_rpn();
+            assert_eq!(Expression::parse_rpn(&rpn).unwrap(), expr, "{} written as {}", case, rpn);
+        }
+    }
+
+    #[test]
+    fn words() {
+        assert_eq!(parse("(1 + 2) * -x").to_rpn(), "1 2 + x neg *");
+        assert_eq!(parse("max(1, f())").to_rpn(), "1 f@0 max@2");
+        // operators need no whitespace around them
+        assert_eq!(Expression::parse_rpn("1 2+3*").unwrap(), parse("(1 + 2) * 3"));
+    }
+}
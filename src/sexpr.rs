@@ -0,0 +1,163 @@
+use std::{iter::Peekable, slice::Iter};
+
+use crate::{
+    error::ParseError,
+    expression::{ExprKind, Expression},
+    print::quoted,
+    span::Span,
+    token::{tokenize, Token, TokenKind, UnaryOp},
+};
+
+fn write_sexpr(out: &mut String, expr: &Expression) {
+    let list = |out: &mut String, head: &str, items: &[&Expression]| {
+        out.push('(');
+        out.push_str(head);
+        for item in items {
+            out.push(' ');
+            write_sexpr(out, item);
+        }
+        out.push(')');
+    };
+
+    match &expr.kind {
+        ExprKind::Operator { op, lhs, rhs, .. } => list(out, op.symbol(), &[lhs, rhs]),
+        ExprKind::Unary { op, operand, .. } => list(out, op.symbol(), &[operand]),
+        ExprKind::Constant(literal) => out.push_str(&literal.to_string()),
+        ExprKind::Variable(name) => out.push_str(name),
+        ExprKind::Str(s) => out.push_str(&quoted(s)),
+        ExprKind::Call { name, args, .. } => list(out, name, &args.iter().collect::<Vec<_>>()),
+    }
+}
+
+// The rest of a list after its `(`, which is at `open`
+fn parse_list(iter: &mut Peekable<Iter<Token>>, end: Span, open: Span) -> Result<Expression, ParseError> {
+    let Some(head) = iter.next() else {
+        return Err(ParseError::UnmatchedParenOpen(open));
+    };
+    if !matches!(head.kind, TokenKind::Operator(_) | TokenKind::Ident(_)) {
+        return Err(ParseError::ExpectedHead(head.span));
+    }
+
+    let mut items = Vec::new();
+    let close = loop {
+        match iter.peek() {
+            Some(Token { kind: TokenKind::ParenClose, span }) => {
+                iter.next();
+                break *span;
+            },
+            None => return Err(ParseError::UnmatchedParenOpen(open)),
+            _ => items.push(parse_item(iter, end)?),
+        }
+    };
+
+    let span = open.to(close);
+    let kind = match &head.kind {
+        // `(- x)` is a prefix operator, `(- x y)` a binary one
+        &TokenKind::Operator(op) => match (items.len(), UnaryOp::from_op(op)) {
+            (0, _) => return Err(ParseError::MissingLeftOperand(op, head.span)),
+            (1, None) => return Err(ParseError::MissingRightOperand(op, close)),
+            (1, Some(unary)) => ExprKind::Unary { op: unary, op_span: head.span, operand: Box::new(items.remove(0)) },
+            (2, _) => {
+                let rhs = items.pop().unwrap();
+                let lhs = items.pop().unwrap();
+                ExprKind::Operator { op, op_span: head.span, lhs: Box::new(lhs), rhs: Box::new(rhs) }
+            },
+            _ => return Err(ParseError::ExtraOperand(op, items[2].span)),
+        },
+        TokenKind::Ident(name) => ExprKind::Call { name: name.clone(), name_span: head.span, args: items },
+        _ => unreachable!(),
+    };
+
+    Ok(Expression { kind, span })
+}
+
+fn parse_item(iter: &mut Peekable<Iter<Token>>, end: Span) -> Result<Expression, ParseError> {
+    let Some(token) = iter.next() else {
+        return Err(ParseError::ExpectedOperand(end));
+    };
+
+    let kind = match &token.kind {
+        TokenKind::Constant(literal) => ExprKind::Constant(*literal),
+        TokenKind::Ident(name) => ExprKind::Variable(name.clone()),
+        TokenKind::Str(s) => ExprKind::Str(s.clone()),
+        TokenKind::ParenOpen => return parse_list(iter, end, token.span),
+        TokenKind::ParenClose => return Err(ParseError::UnmatchedParenClose(token.span)),
+        // operators only head a list
+        TokenKind::Operator(_) | TokenKind::Comma => return Err(ParseError::ExpectedOperand(token.span)),
+    };
+
+    Ok(Expression { kind, span: token.span })
+}
+
+impl Expression {
+    // Prefix notation as S-expressions, e.g. `(+ 1 (* 2 (- x)))`. Operators and calls
+    // are lists headed by the operator symbol or function name, `(max 1 2)`.
+    pub fn to_sexpr(&self) -> String {
+        let mut out = String::new();
+        write_sexpr(&mut out, self);
+        out
+    }
+
+    // Parses what `to_sexpr` writes, with the same tokens as infix input
+    pub fn parse_sexpr(s: &str) -> Result<Self, ParseError> {
+        let tokens = tokenize(s)?;
+        let end = tokens.last().map_or(0, |token| token.span.end);
+        let end = Span::new(end, end);
+
+        let mut iter = tokens.iter().peekable();
+        let expr = parse_item(&mut iter, end)?;
+
+        let mut leftover = Vec::new();
+        while iter.peek().is_some() {
+            leftover.push(parse_item(&mut iter, end)?);
+        }
+
+        match (leftover.first(), leftover.last()) {
+            (Some(first), Some(last)) => Err(ParseError::LeftoverOperands(leftover.len(), first.span.to(last.span))),
+            _ => Ok(expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expression::Expression, token::tokenize};
+
+    fn parse(s: &str) -> Expression {
+        tokenize(s).and_then(|tokens| Expression::parse(&tokens)).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let cases = [
+            "1 + 2 * 3",
+            "(1 - 2) - 3",
+            "1 - (2 - 3)",
+            "2 ^ 3 ^ 2",
+            "-(2 ^ 3)",
+            "(-a) ^ -b",
+            "2 ^ -x ^ 2",
+            "a - -b",
+            "-(+(-x))",
+            "(a * b) % c",
+            "(a // b) %% c",
+            "1.5e300 * 2.0",
+            "max(1, -2, (3 + 4) * 5)",
+            "f()",
+            "g(h(x), \"a \\\"b\\\" c\")",
+            "neg + pos",
+        ];
+
+        for case in cases {
+            let expr = parse(case);
+            let sexpr = expr.to_sexpr();
+            assert_eq!(Expression::parse_sexpr(&sexpr).unwrap(), expr, "{} written as {}", case, sexpr);
+        }
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(parse("1 + 2 * -x").to_sexpr(), "(+ 1 (* 2 (- x)))");
+        assert_eq!(parse("max(1, f())").to_sexpr(), "(max 1 (f))");
+    }
+}
@@ -0,0 +1,296 @@
+use std::fmt;
+
+use crate::{error::ParseError, span::Span};
+
+// Operators
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    // `/`, rounds toward zero: `-7 / 2 == -3`
+    Div,
+    // `%`, remainder of `Div`, takes the sign of the dividend: `-7 % 2 == -1`
+    Rem,
+    // `//`, rounds toward negative infinity: `-7 // 2 == -4`
+    FloorDiv,
+    // `%%`, remainder of `FloorDiv`, takes the sign of the divisor: `-7 %% 2 == 1`
+    Mod,
+    Pow,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+}
+
+impl Op {
+    // Precedence table, higher binds tighter.
+    // Adding an operator only takes a new row here and in `SPELLINGS`.
+    // Unary operators sit between `Div` and `Pow`, see `UnaryOp::PRECEDENCE`.
+    const TABLE: &'static [(Op, u8, Assoc)] = &[
+        (Op::Add, 1, Assoc::Left),
+        (Op::Sub, 1, Assoc::Left),
+        (Op::Mul, 2, Assoc::Left),
+        (Op::Div, 2, Assoc::Left),
+        (Op::Rem, 2, Assoc::Left),
+        (Op::FloorDiv, 2, Assoc::Left),
+        (Op::Mod, 2, Assoc::Left),
+        (Op::Pow, 4, Assoc::Right),
+    ];
+
+    fn entry(self) -> (u8, Assoc) {
+        Self::TABLE.iter()
+            .find(|(op, _, _)| *op == self)
+            .map(|&(_, precedence, assoc)| (precedence, assoc))
+            .unwrap()
+    }
+
+    pub fn precedence(self) -> u8 {
+        self.entry().0
+    }
+
+    pub fn associativity(self) -> Assoc {
+        self.entry().1
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+            Op::Rem => "%",
+            Op::FloorDiv => "//",
+            Op::Mod => "%%",
+            Op::Pow => "^",
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+// Prefix operators, spelled like their binary counterparts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum UnaryOp {
+    Neg,
+    Pos,
+}
+
+impl UnaryOp {
+    // Binds tighter than `*` but looser than `^`, so `-2^2` is `-(2^2)`
+    pub const PRECEDENCE: u8 = 3;
+
+    // A binary operator found where an operand is expected
+    pub fn from_op(op: Op) -> Option<Self> {
+        match op {
+            Op::Sub => Some(UnaryOp::Neg),
+            Op::Add => Some(UnaryOp::Pos),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            UnaryOp::Neg => "-",
+            UnaryOp::Pos => "+",
+        }
+    }
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+// A number as written: integers stay exact until evaluation picks a mode
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
+pub enum Literal {
+    Int(u64),
+    Float(f64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
+pub enum TokenKind {
+    Operator(Op),
+
+    Constant(Literal),
+    Ident(String),
+    // `"..."` with `\"` and `\\` unescaped
+    Str(String),
+
+    ParenOpen,
+    ParenClose,
+    Comma,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+// Returns the end of the number literal starting at `start`:
+// digits, an optional fraction and an optional exponent (`12`, `1.5`, `.25`, `6.02e23`)
+fn scan_number(s: &[u8], start: usize) -> (usize, bool) {
+    let digits = |mut i: usize| {
+        while i < s.len() && s[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+
+    let mut end = digits(start);
+    let mut is_float = false;
+
+    if s.get(end) == Some(&b'.') {
+        end = digits(end + 1);
+        is_float = true;
+    }
+
+    // only an exponent if digits follow, so `2e` is left alone
+    if matches!(s.get(end), Some(b'e' | b'E')) {
+        let mut i = end + 1;
+        if matches!(s.get(i), Some(b'+' | b'-')) {
+            i += 1;
+        }
+        if s.get(i).is_some_and(u8::is_ascii_digit) {
+            end = digits(i);
+            is_float = true;
+        }
+    }
+
+    (end, is_float)
+}
+
+// Unescapes the string literal whose opening `"` is at `start`, returns it with the end of the literal
+pub(crate) fn scan_string(s: &str, start: usize) -> Result<(String, usize), ParseError> {
+    let mut iter = s[start + 1..].char_indices().map(|(i, c)| (start + 1 + i, c));
+    let mut string = String::new();
+
+    loop {
+        match iter.next() {
+            Some((i, '"')) => return Ok((string, i + 1)),
+            Some((_, '\\')) => match iter.next() {
+                Some((_, c @ ('"' | '\\'))) => string.push(c),
+                Some((i, c)) => return Err(ParseError::UnknownEscape(c, Span::new(i - 1, i + c.len_utf8()))),
+                None => return Err(ParseError::UnterminatedString(Span::new(start, s.len()))),
+            },
+            Some((_, c)) => string.push(c),
+            None => return Err(ParseError::UnterminatedString(Span::new(start, s.len()))),
+        }
+    }
+}
+
+// Operator spellings, longer ones first so `**` is not read as two `*`
+const SPELLINGS: &[(&str, Op)] = &[
+    ("**", Op::Pow),
+    ("//", Op::FloorDiv),
+    ("%%", Op::Mod),
+    ("+", Op::Add),
+    ("-", Op::Sub),
+    ("*", Op::Mul),
+    ("/", Op::Div),
+    ("%", Op::Rem),
+    ("^", Op::Pow),
+];
+
+pub fn tokenize(s: &str) -> Result<Vec<Token>, ParseError> {
+    tokenize_from(s, 0)
+}
+
+// Like `tokenize`, for `s` from byte `offset` on, spans still point into all of `s`
+pub(crate) fn tokenize_from(s: &str, offset: usize) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut iter = s[offset..].char_indices().map(|(i, c)| (offset + i, c)).peekable();
+
+    while let Some(&(start, c)) = iter.peek() {
+        // parse a number
+        if c.is_ascii_digit() || (c == '.' && s[start + 1..].starts_with(|d: char| d.is_ascii_digit())) {
+            let (end, is_float) = scan_number(s.as_bytes(), start);
+            while iter.next_if(|&(i, _)| i < end).is_some() {}
+
+            let span = Span::new(start, end);
+            let text = &s[start..end];
+            let literal = match text.parse() {
+                Ok(n) if !is_float => Some(Literal::Int(n)),
+                // integers too wide for `u64` are kept as floats, integer mode reports them out of bounds
+                _ => {
+                    let f: f64 = text.parse().unwrap();
+                    // overflowing literals parse as infinity
+                    f.is_finite().then_some(Literal::Float(f))
+                },
+            };
+
+            tokens.push(Token {
+                kind: TokenKind::Constant(literal.ok_or(ParseError::OutOfBounds(span))?),
+                span,
+            });
+            continue;
+        }
+
+        // parse an identifier
+        if c.is_ascii_alphabetic() || c == '_' {
+            let end = s[start..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .map_or(s.len(), |len| start + len);
+            while iter.next_if(|&(i, _)| i < end).is_some() {}
+
+            tokens.push(Token {
+                kind: TokenKind::Ident(s[start..end].to_string()),
+                span: Span::new(start, end),
+            });
+            continue;
+        }
+
+        // parse a string literal
+        if c == '"' {
+            let (string, end) = scan_string(s, start)?;
+            while iter.next_if(|&(i, _)| i < end).is_some() {}
+
+            tokens.push(Token { kind: TokenKind::Str(string), span: Span::new(start, end) });
+            continue;
+        }
+
+        // parse an operator
+        let spelling = SPELLINGS.iter().find(|(spelling, _)| s[start..].starts_with(spelling));
+        if let Some(&(spelling, op)) = spelling {
+            let span = Span::new(start, start + spelling.len());
+            while iter.next_if(|&(i, _)| i < span.end).is_some() {}
+
+            tokens.push(Token { kind: TokenKind::Operator(op), span });
+            continue;
+        }
+
+        // parse a parenthesis / comma
+        if !c.is_whitespace() {
+            let span = Span::new(start, start + c.len_utf8());
+            let kind = match c {
+                '(' => TokenKind::ParenOpen,
+                ')' => TokenKind::ParenClose,
+                ',' => TokenKind::Comma,
+                _   => return Err(ParseError::UnknownOperator(c, span)),
+            };
+            tokens.push(Token { kind, span });
+        }
+
+        iter.next();
+    }
+
+    Ok(tokens)
+}
@@ -0,0 +1,165 @@
+use crate::{
+    expression::{ExprKind, Expression},
+    print::{typeset_needs_parens, typeset_unary_needs_parens, Side},
+    token::{Literal, Op, UnaryOp},
+};
+
+fn group(out: &mut String, expr: &Expression, parens: bool) {
+    if parens {
+        out.push('(');
+        typst(out, expr);
+        out.push(')');
+    } else {
+        typst(out, expr);
+    }
+}
+
+fn args(out: &mut String, args: &[Expression]) {
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        typst(out, arg);
+    }
+}
+
+fn text(out: &mut String, s: &str) {
+    out.push('"');
+    out.push_str(&s.replace('\\', "\\\\").replace('"', "\\\""));
+    out.push('"');
+}
+
+fn call(out: &mut String, name: &str, call_args: &[Expression]) {
+    // functions Typst has its own notation for
+    match (name, call_args) {
+        ("sqrt" | "abs" | "floor" | "ceil", [x]) => {
+            out.push_str(name);
+            group(out, x, true);
+            return;
+        },
+        ("cbrt", [x]) => {
+            out.push_str("root(3, ");
+            typst(out, x);
+            out.push(')');
+            return;
+        },
+        ("exp", [x]) => {
+            out.push_str("e^");
+            group(out, x, true);
+            return;
+        },
+        _ => {},
+    }
+
+    let call_args = match (name, call_args) {
+        ("log2", _) => {
+            out.push_str("log_2");
+            call_args
+        },
+        ("log10", _) => {
+            out.push_str("log_10");
+            call_args
+        },
+        ("log", [x, base]) => {
+            out.push_str("log_(");
+            typst(out, base);
+            out.push(')');
+            std::slice::from_ref(x)
+        },
+        ("asin" | "acos" | "atan", _) => {
+            out.push_str("arc");
+            out.push_str(&name[1..]);
+            call_args
+        },
+        // operators predefined in math mode, set upright
+        ("sin" | "cos" | "tan" | "sinh" | "cosh" | "tanh" | "ln" | "log" | "min" | "max" | "gcd" | "lcm", _) => {
+            out.push_str(name);
+            call_args
+        },
+        _ => {
+            out.push_str("op(");
+            text(out, name);
+            out.push(')');
+            call_args
+        },
+    };
+
+    out.push('(');
+    args(out, call_args);
+    out.push(')');
+}
+
+fn typst(out: &mut String, expr: &Expression) {
+    match &expr.kind {
+        ExprKind::Operator { op: Op::Div, lhs, rhs, .. } => {
+            out.push_str("frac(");
+            typst(out, lhs);
+            out.push_str(", ");
+            typst(out, rhs);
+            out.push(')');
+        },
+        ExprKind::Operator { op: Op::FloorDiv, lhs, rhs, .. } => {
+            out.push_str("floor(frac(");
+            typst(out, lhs);
+            out.push_str(", ");
+            typst(out, rhs);
+            out.push_str("))");
+        },
+        ExprKind::Operator { op: Op::Pow, lhs, rhs, .. } => {
+            group(out, lhs, typeset_needs_parens(Op::Pow, lhs, Side::Left));
+            // parentheses around an attachment are not shown
+            out.push_str("^(");
+            typst(out, rhs);
+            out.push(')');
+        },
+        ExprKind::Operator { op, lhs, rhs, .. } => {
+            group(out, lhs, typeset_needs_parens(*op, lhs, Side::Left));
+            out.push_str(match op {
+                Op::Add => " + ",
+                Op::Sub => " - ",
+                Op::Mul => " dot ",
+                Op::Rem => " op(\"rem\") ",
+                Op::Mod => " mod ",
+                Op::Div | Op::FloorDiv | Op::Pow => unreachable!(),
+            });
+            group(out, rhs, typeset_needs_parens(*op, rhs, Side::Right));
+        },
+        ExprKind::Unary { op, operand, .. } => {
+            out.push_str(match op {
+                UnaryOp::Neg => "-",
+                UnaryOp::Pos => "+",
+            });
+            group(out, operand, typeset_unary_needs_parens(operand));
+        },
+        ExprKind::Constant(Literal::Float(x)) => {
+            // `6.02e23` as `6.02 times 10^(23)`
+            let s = format!("{:?}", x);
+            match s.split_once('e') {
+                Some((mantissa, exponent)) => out.push_str(&format!("{} times 10^({})", mantissa, exponent)),
+                None => out.push_str(&s),
+            }
+        },
+        ExprKind::Constant(literal) => out.push_str(&literal.to_string()),
+        ExprKind::Variable(name) => match name.as_str() {
+            "pi" | "tau" | "phi" => out.push_str(name),
+            // `inf` is the infimum in Typst
+            "inf" => out.push_str("infinity"),
+            "nan" => out.push_str("\"NaN\""),
+            // several letters in a row would be read as a Typst variable
+            _ if name.chars().count() == 1 => out.push_str(name),
+            _ => text(out, name),
+        },
+        ExprKind::Str(s) => text(out, &format!("\"{}\"", s)),
+        ExprKind::Call { name, args, .. } => call(out, name, args),
+    }
+}
+
+impl Expression {
+    // Typst math-mode source, to be put between `$`s: `/` as `frac()`, `^` as an
+    // attachment and `*` as `dot`, parenthesized only where needed
+    pub fn to_typst(&self) -> String {
+        let mut out = String::new();
+        typst(&mut out, self);
+        out
+    }
+}